@@ -1,5 +1,5 @@
 use criterion::{criterion_group, criterion_main, Criterion};
-use neighbourhood::KdIndexTree;
+use neighbourhood::{Boundary, KdIndexTree};
 
 pub mod util;
 use util::random_points;
@@ -25,7 +25,8 @@ fn neighbourhood_query(c: &mut Criterion) {
         let points: Vec<[f64; 3]> = random_points(NUM_POINTS, -10., 10., SEED);
         let kd_tree = KdIndexTree::new(&points);
         b.iter(|| {
-            let neighbours = kd_tree.neighbourhood_by_index(&[0., 0., 0.], EPSILON);
+            let neighbours =
+                kd_tree.neighbourhood_by_index(&[0., 0., 0.], EPSILON, Boundary::Closed);
             std::hint::black_box(neighbours);
         });
     });
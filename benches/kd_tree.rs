@@ -1,5 +1,5 @@
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
-use neighbourhood::KdTree;
+use neighbourhood::{Boundary, KdTree, KdTreeBuilder};
 
 pub mod util;
 use util::random_points;
@@ -25,7 +25,7 @@ fn neighbourhood_query(c: &mut Criterion) {
         let points: Vec<[f64; 3]> = random_points(NUM_POINTS, -10., 10., SEED);
         let kd_tree = KdTree::new(points);
         b.iter(|| {
-            let neighbours = kd_tree.neighbourhood(&[0., 0., 0.], EPSILON);
+            let neighbours = kd_tree.neighbourhood(&[0., 0., 0.], EPSILON, Boundary::Closed);
             std::hint::black_box(neighbours);
         });
     });
@@ -39,7 +39,7 @@ fn count_neighbourhood_query(c: &mut Criterion) {
         let points: Vec<[f64; 3]> = random_points(NUM_POINTS, -10., 10., SEED);
         let kd_tree = KdTree::new(points);
         b.iter(|| {
-            let neighbours = kd_tree.count_neighbourhood(&[0., 0., 0.], EPSILON);
+            let neighbours = kd_tree.count_neighbourhood(&[0., 0., 0.], EPSILON, Boundary::Closed);
             std::hint::black_box(neighbours);
         });
     });
@@ -60,7 +60,7 @@ fn optimal_brute_force_size(c: &mut Criterion) {
                 kd_tree.brute_force_size = *brute_force_size;
                 b.iter(|| {
                     for p in &points[99_000..101_000] {
-                        let neighbours = kd_tree.neighbourhood(p, EPSILON);
+                        let neighbours = kd_tree.neighbourhood(p, EPSILON, Boundary::Closed);
                         std::hint::black_box(neighbours);
                     }
                 });
@@ -83,12 +83,128 @@ fn knn_query(c: &mut Criterion) {
     });
 }
 
+fn bounding_boxes(c: &mut Criterion) {
+    const NUM_POINTS: usize = 200_000;
+    const EPSILON: f64 = 0.5;
+    const K: usize = 10;
+
+    // Points on the surface of a cube, whose interior leaves empty space the split
+    // planes alone do not bound tightly.
+    let mut points: Vec<[f64; 3]> = random_points(NUM_POINTS, -10., 10., 0);
+    for (i, point) in points.iter_mut().enumerate() {
+        point[i % 3] = if i % 2 == 0 { -10.0 } else { 10.0 };
+    }
+    let queries: Vec<[f64; 3]> = random_points(1_000, -10., 10., 1);
+
+    let mut group = c.benchmark_group("BoundingBoxes");
+    for bounding_boxes in [false, true] {
+        let kd_tree = KdTreeBuilder::new()
+            .bounding_boxes(bounding_boxes)
+            .build(points.clone());
+        group.bench_with_input(
+            BenchmarkId::new("Knn", bounding_boxes),
+            &kd_tree,
+            |b, kd_tree| {
+                b.iter(|| {
+                    for p in &queries {
+                        std::hint::black_box(kd_tree.knn(p, K));
+                    }
+                });
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("Neighbourhood", bounding_boxes),
+            &kd_tree,
+            |b, kd_tree| {
+                b.iter(|| {
+                    for p in &queries {
+                        std::hint::black_box(kd_tree.neighbourhood(p, EPSILON, Boundary::Closed));
+                    }
+                });
+            },
+        );
+    }
+}
+
+/// Leaf scans with large leaves, compare with and without the `simd` feature.
+fn leaf_scan(c: &mut Criterion) {
+    const NUM_POINTS: usize = 200_000;
+    const K: usize = 10;
+    const EPSILON: f32 = 1.0;
+    const BRUTE_FORCE_SIZE: usize = 64;
+
+    fn bench<const N: usize>(c: &mut Criterion, name: &str) {
+        let points: Vec<[f32; N]> = random_points(NUM_POINTS, -10., 10., 0);
+        let queries: Vec<[f32; N]> = random_points(1_000, -10., 10., 1);
+        let kd_tree = KdTree::with_brute_force_size(points, BRUTE_FORCE_SIZE);
+        c.bench_function(&format!("LeafScan/Knn/{name}"), |b| {
+            b.iter(|| {
+                for p in &queries {
+                    std::hint::black_box(kd_tree.knn(p, K));
+                }
+            });
+        });
+        c.bench_function(&format!("LeafScan/CountNeighbourhood/{name}"), |b| {
+            b.iter(|| {
+                for p in &queries {
+                    std::hint::black_box(kd_tree.count_neighbourhood(p, EPSILON, Boundary::Closed));
+                }
+            });
+        });
+    }
+
+    bench::<3>(c, "3");
+    bench::<8>(c, "8");
+}
+
+/// Queries on a tree that is much larger than the caches, with and without a contiguous
+/// copy of the top levels.
+fn top_levels(c: &mut Criterion) {
+    const NUM_POINTS: usize = 10_000_000;
+    const EPSILON: f64 = 0.05;
+
+    let points: Vec<[f64; 3]> = random_points(NUM_POINTS, -10., 10., 0);
+    let queries: Vec<[f64; 3]> = random_points(10_000, -10., 10., 1);
+
+    let mut group = c.benchmark_group("TopLevels");
+    for levels in [0, 16] {
+        let kd_tree = KdTreeBuilder::new()
+            .top_levels(levels)
+            .build(points.clone());
+        group.bench_with_input(
+            BenchmarkId::new("Nearest", levels),
+            &kd_tree,
+            |b, kd_tree| {
+                b.iter(|| {
+                    for p in &queries {
+                        std::hint::black_box(kd_tree.nearest(p));
+                    }
+                });
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("Neighbourhood", levels),
+            &kd_tree,
+            |b, kd_tree| {
+                b.iter(|| {
+                    for p in &queries {
+                        std::hint::black_box(kd_tree.neighbourhood(p, EPSILON, Boundary::Closed));
+                    }
+                });
+            },
+        );
+    }
+}
+
 criterion_group!(
     benches,
     buildup,
     neighbourhood_query,
     count_neighbourhood_query,
     optimal_brute_force_size,
-    knn_query
+    knn_query,
+    bounding_boxes,
+    leaf_scan,
+    top_levels
 );
 criterion_main!(benches);
@@ -361,7 +361,7 @@ fn benchmark_nh_kdtree(
     for eps in epsilons {
         let now = Instant::now();
         for p in query_points {
-            let r = nh_kd_tree.neighbourhood(p, *eps);
+            let r = nh_kd_tree.neighbourhood(p, *eps, neighbourhood::Boundary::Closed);
             std::hint::black_box(r);
         }
         let timing = now.elapsed();
@@ -398,7 +398,8 @@ fn benchmark_nh_kdindextree(
     for eps in epsilons {
         let now = Instant::now();
         for p in query_points {
-            let r = nh_kd_index_tree.neighbourhood_by_index(p, *eps);
+            let r =
+                nh_kd_index_tree.neighbourhood_by_index(p, *eps, neighbourhood::Boundary::Closed);
             std::hint::black_box(r);
         }
         let timing = now.elapsed();
@@ -93,7 +93,10 @@ pub trait UnifiedKdTreeTestApi<T: num_traits::Float + std::fmt::Debug, const N:
 pub mod nh {
     pub struct KdTree<T: num_traits::Float, const N: usize>(neighbourhood::KdTree<T, N>);
 
-    impl<T: num_traits::Float + std::fmt::Debug, const N: usize> KdTree<T, N> {
+    impl<T, const N: usize> KdTree<T, N>
+    where
+        T: num_traits::Float + std::fmt::Debug + neighbourhood::Coordinate<Distance = T>,
+    {
         pub fn new(data: &[[T; N]]) -> Self {
             Self(neighbourhood::KdTree::with_brute_force_size(
                 data.to_vec(),
@@ -102,18 +105,22 @@ pub mod nh {
         }
     }
 
-    impl<T: num_traits::Float + std::fmt::Debug, const N: usize> crate::UnifiedKdTreeTestApi<T, N>
-        for KdTree<T, N>
+    impl<T, const N: usize> crate::UnifiedKdTreeTestApi<T, N> for KdTree<T, N>
+    where
+        T: num_traits::Float + std::fmt::Debug + neighbourhood::Coordinate<Distance = T>,
     {
         fn query_within(&self, p: &[T; N], eps: T, _: &[[T; N]]) -> Vec<[T; N]> {
-            let result = self.0.neighbourhood(p, eps);
+            let result = self
+                .0
+                .neighbourhood(p, eps, neighbourhood::Boundary::Closed);
             let mut points: Vec<_> = result.into_iter().cloned().collect();
             crate::sort_query_result(p, &mut points);
             points
         }
 
         fn count_within(&self, p: &[T; N], eps: T) -> usize {
-            self.0.count_neighbourhood(p, eps)
+            self.0
+                .count_neighbourhood(p, eps, neighbourhood::Boundary::Closed)
         }
 
         fn knn(&self, p: &[T; N], k: usize, _: &[[T; N]]) -> Vec<[T; N]> {
@@ -126,7 +133,10 @@ pub mod nh {
 
     pub struct KdTreeByIndex<T: num_traits::Float, const N: usize>(neighbourhood::KdTree<T, N>);
 
-    impl<T: num_traits::Float + std::fmt::Debug, const N: usize> KdTreeByIndex<T, N> {
+    impl<T, const N: usize> KdTreeByIndex<T, N>
+    where
+        T: num_traits::Float + std::fmt::Debug + neighbourhood::Coordinate<Distance = T>,
+    {
         pub fn new(data: &[[T; N]]) -> Self {
             Self(neighbourhood::KdTree::with_brute_force_size(
                 data.to_vec(),
@@ -135,11 +145,14 @@ pub mod nh {
         }
     }
 
-    impl<T: num_traits::Float + std::fmt::Debug, const N: usize> crate::UnifiedKdTreeTestApi<T, N>
-        for KdTreeByIndex<T, N>
+    impl<T, const N: usize> crate::UnifiedKdTreeTestApi<T, N> for KdTreeByIndex<T, N>
+    where
+        T: num_traits::Float + std::fmt::Debug + neighbourhood::Coordinate<Distance = T>,
     {
         fn query_within(&self, p: &[T; N], eps: T, _: &[[T; N]]) -> Vec<[T; N]> {
-            let result = self.0.neighbourhood_by_index(p, eps);
+            let result = self
+                .0
+                .neighbourhood_by_index(p, eps, neighbourhood::Boundary::Closed);
             let mut points: Vec<_> = result.into_iter().map(|i| self.0.data()[i]).collect();
             crate::sort_query_result(p, &mut points);
             points
@@ -161,29 +174,36 @@ pub mod nh {
         neighbourhood::KdIndexTree<'a, T, N>,
     );
 
-    impl<'a, T: num_traits::Float + std::fmt::Debug, const N: usize> KdIndexTree<'a, T, N> {
+    impl<'a, T, const N: usize> KdIndexTree<'a, T, N>
+    where
+        T: num_traits::Float + std::fmt::Debug + neighbourhood::Coordinate<Distance = T>,
+    {
         pub fn new(data: &'a [[T; N]]) -> Self {
             Self(neighbourhood::KdIndexTree::with_brute_force_size(data, 0))
         }
     }
 
-    impl<T: num_traits::Float + std::fmt::Debug, const N: usize> crate::UnifiedKdTreeTestApi<T, N>
-        for KdIndexTree<'_, T, N>
+    impl<T, const N: usize> crate::UnifiedKdTreeTestApi<T, N> for KdIndexTree<'_, T, N>
+    where
+        T: num_traits::Float + std::fmt::Debug + neighbourhood::Coordinate<Distance = T>,
     {
         fn query_within(&self, p: &[T; N], eps: T, _: &[[T; N]]) -> Vec<[T; N]> {
-            let result = self.0.neighbourhood_by_index(p, eps);
-            let mut points: Vec<_> = result.into_iter().map(|i| self.0.data[i]).collect();
+            let result = self
+                .0
+                .neighbourhood_by_index(p, eps, neighbourhood::Boundary::Closed);
+            let mut points: Vec<_> = result.into_iter().map(|i| self.0.data()[i]).collect();
             crate::sort_query_result(p, &mut points);
             points
         }
 
         fn count_within(&self, p: &[T; N], eps: T) -> usize {
-            self.0.count_neighbourhood(p, eps)
+            self.0
+                .count_neighbourhood(p, eps, neighbourhood::Boundary::Closed)
         }
 
         fn knn(&self, p: &[T; N], k: usize, _: &[[T; N]]) -> Vec<[T; N]> {
             let result = self.0.knn_by_index(p, k);
-            let mut points: Vec<_> = result.into_iter().map(|v| self.0.data[v.1]).collect();
+            let mut points: Vec<_> = result.into_iter().map(|v| self.0.data()[v.1]).collect();
             crate::sort_query_result(p, &mut points);
             points
         }
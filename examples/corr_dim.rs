@@ -2,6 +2,8 @@
 // https://en.wikipedia.org/wiki/Correlation_dimension of the
 // Lorenz Attractor.
 
+use neighbourhood::Boundary;
+
 fn lorenz(dt: f64, xyz: [f64; 3]) -> [f64; 3] {
     let sigma = 10.;
     let beta = 8. / 3.;
@@ -31,7 +33,7 @@ fn main() {
     let mut counts = [(1.0, 0), (4.0, 0)];
     for data_point in kd_tree.data() {
         for count in counts.iter_mut() {
-            count.1 += kd_tree.count_neighbourhood(data_point, count.0);
+            count.1 += kd_tree.count_neighbourhood(data_point, count.0, Boundary::Closed);
         }
     }
 
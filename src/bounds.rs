@@ -0,0 +1,169 @@
+use alloc::vec::Vec;
+
+use crate::{split::Splits, Coordinate};
+
+/// Tight bounding box of a subtree, stored in pre-order of the boxed subtrees.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct NodeBox<T, const N: usize> {
+    bounds: [[T; N]; 2],
+    /// Number of boxes stored for the left subtree, which directly follow this one.
+    left_count: usize,
+}
+
+/// Bounding boxes stored by a tree for all subtrees with more than `min_len` points,
+/// see [NodeBoxes].
+#[derive(Debug, Clone)]
+pub(crate) struct BoundingBoxes<T, const N: usize> {
+    boxes: Vec<NodeBox<T, N>>,
+    min_len: usize,
+}
+
+impl<T: Coordinate, const N: usize> BoundingBoxes<T, N> {
+    /// No boxes are stored, queries prune with the split planes only.
+    pub(crate) fn none() -> Self {
+        BoundingBoxes {
+            boxes: Vec::new(),
+            min_len: usize::MAX,
+        }
+    }
+
+    /// Computes the boxes of all subtrees with more than `min_len` of the `len` points
+    /// returned by `point(position)`.
+    pub(crate) fn new(
+        len: usize,
+        point: &impl Fn(usize) -> [T; N],
+        splits: Splits,
+        min_len: usize,
+    ) -> Self {
+        let min_len = min_len.max(1);
+        let mut boxes = Vec::new();
+        if len > min_len {
+            fill_recursive(&mut boxes, 0, len, point, splits, min_len);
+        }
+        BoundingBoxes { boxes, min_len }
+    }
+
+    pub(crate) fn node_boxes(&self) -> NodeBoxes<'_, T, N> {
+        NodeBoxes {
+            boxes: &self.boxes,
+            min_len: self.min_len,
+        }
+    }
+}
+
+/// The bounding boxes of an implicitly stored subtree. Follows the recursion of the
+/// queries alongside [Splits].
+#[derive(Debug)]
+pub(crate) struct NodeBoxes<'a, T, const N: usize> {
+    boxes: &'a [NodeBox<T, N>],
+    min_len: usize,
+}
+
+impl<T, const N: usize> Clone for NodeBoxes<'_, T, N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, const N: usize> Copy for NodeBoxes<'_, T, N> {}
+
+impl<'a, T, const N: usize> NodeBoxes<'a, T, N> {
+    /// Bounding box of the subtree of length `len`, if it is stored.
+    #[inline]
+    pub(crate) fn bounds(&self, len: usize) -> Option<&'a [[T; N]; 2]> {
+        if len > self.min_len {
+            Some(&self.boxes[0].bounds)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the boxes of both child subtrees of a subtree of length `len`.
+    #[inline]
+    pub(crate) fn split(self, len: usize) -> (Self, Self) {
+        if len > self.min_len {
+            let left_count = self.boxes[0].left_count;
+            (
+                NodeBoxes {
+                    boxes: &self.boxes[1..(1 + left_count)],
+                    min_len: self.min_len,
+                },
+                NodeBoxes {
+                    boxes: &self.boxes[(1 + left_count)..],
+                    min_len: self.min_len,
+                },
+            )
+        } else {
+            (self, self)
+        }
+    }
+}
+
+/// Appends the boxes of the subtree at `[offset, offset + len)` in pre-order and returns
+/// its bounding box.
+fn fill_recursive<T: Coordinate, const N: usize>(
+    boxes: &mut Vec<NodeBox<T, N>>,
+    offset: usize,
+    len: usize,
+    point: &impl Fn(usize) -> [T; N],
+    splits: Splits,
+    min_len: usize,
+) -> [[T; N]; 2] {
+    let mut bounds = [[T::MAX; N], [T::MIN; N]];
+    if len <= min_len {
+        for position in offset..(offset + len) {
+            extend(&mut bounds, &point(position));
+        }
+        return bounds;
+    }
+
+    let index = boxes.len();
+    boxes.push(NodeBox {
+        bounds,
+        left_count: 0,
+    });
+    let (split_index, _, splits1, splits2) = splits.split::<N>(len);
+    let bounds1 = fill_recursive(boxes, offset, split_index, point, splits1, min_len);
+    let left_count = boxes.len() - index - 1;
+    let bounds2 = fill_recursive(
+        boxes,
+        offset + split_index + 1,
+        len - split_index - 1,
+        point,
+        splits2,
+        min_len,
+    );
+
+    extend(&mut bounds, &point(offset + split_index));
+    for child_bounds in [bounds1, bounds2] {
+        merge(&mut bounds, &child_bounds);
+    }
+    boxes[index] = NodeBox { bounds, left_count };
+    bounds
+}
+
+#[allow(clippy::needless_range_loop)]
+fn extend<T: Coordinate, const N: usize>(bounds: &mut [[T; N]; 2], point: &[T; N]) {
+    for i in 0..N {
+        if point[i] < bounds[0][i] {
+            bounds[0][i] = point[i];
+        }
+        if point[i] > bounds[1][i] {
+            bounds[1][i] = point[i];
+        }
+    }
+}
+
+/// Extends `bounds` to contain `other`. Empty bounds, as computed for empty subtrees,
+/// leave `bounds` unchanged.
+#[allow(clippy::needless_range_loop)]
+fn merge<T: Coordinate, const N: usize>(bounds: &mut [[T; N]; 2], other: &[[T; N]; 2]) {
+    for i in 0..N {
+        if other[0][i] < bounds[0][i] {
+            bounds[0][i] = other[0][i];
+        }
+        if other[1][i] > bounds[1][i] {
+            bounds[1][i] = other[1][i];
+        }
+    }
+}
@@ -0,0 +1,728 @@
+use alloc::{borrow::Cow, vec, vec::Vec};
+use core::marker::PhantomData;
+use num_traits::Float;
+
+use crate::{
+    bounds::BoundingBoxes,
+    knn_radii::TreeId,
+    prealloc_capacity,
+    split::{bucket_leaf_split_index, ExplicitSplit, SplitLayout},
+    top_levels::TopLevels,
+    Coordinate, GenericKdTree, IndexRangeError, KdIndexTree, KdTree, KdTreeMut, PointChunks,
+    PointSource, SplitAxis, TreeIndex,
+};
+
+/// Determines where the points of a subtree are split during construction.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub enum SplitStrategy {
+    /// Split at the median along the split axis. Produces balanced trees.
+    #[default]
+    Median,
+    /// Split at the middle of the extent along the split axis, sliding the split to the
+    /// closest point if all points lie on one side. Adapts to clustered data at the cost
+    /// of a deeper tree. Stores 8 bytes per subtree larger than a leaf and supports at
+    /// most `u32::MAX` points.
+    SlidingMidpoint,
+    /// Split such that the left subtree of every node is a perfect tree whose leaves hold
+    /// exactly `leaf_size` points. Only the leaves along the right edges may be partially
+    /// filled.
+    BucketLeaf,
+}
+
+/// Builder for [KdTree] and [KdIndexTree] with configurable construction.
+///
+/// ```
+/// use neighbourhood::{KdTreeBuilder, SplitAxis, SplitStrategy};
+///
+/// let data = vec![[0.0, 0.0], [1.0, 0.5], [0.2, 3.0], [4.0, 1.0]];
+/// let kd_tree = KdTreeBuilder::new()
+///     .split_strategy(SplitStrategy::SlidingMidpoint)
+///     .split_axis(SplitAxis::MaxSpread)
+///     .leaf_size(2)
+///     .build(data);
+/// let (_, nearest) = kd_tree.nearest(&[0.9, 0.4]).unwrap();
+/// assert_eq!(nearest, &[1.0, 0.5]);
+/// ```
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct KdTreeBuilder {
+    split_strategy: SplitStrategy,
+    pub(crate) split_axis: SplitAxis,
+    leaf_size: usize,
+    brute_force_size: Option<usize>,
+    bounding_boxes: bool,
+    top_levels: usize,
+}
+
+impl KdTreeBuilder {
+    /// Creates a builder for median split trees with round robin axes and single
+    /// point leaves, which is the layout of [KdTree::new] and [KdIndexTree::new].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets where the points of a subtree are split.
+    pub fn split_strategy(mut self, split_strategy: SplitStrategy) -> Self {
+        self.split_strategy = split_strategy;
+        self
+    }
+
+    /// Sets how the split axis of every node is chosen.
+    pub fn split_axis(mut self, split_axis: SplitAxis) -> Self {
+        self.split_axis = split_axis;
+        self
+    }
+
+    /// Sets the number of points up to which a subtree is not split any further.
+    /// Queries always scan such leaves by brute force, independent of the
+    /// `brute_force_size` of the tree.
+    pub fn leaf_size(mut self, leaf_size: usize) -> Self {
+        self.leaf_size = leaf_size;
+        self
+    }
+
+    /// Sets the `brute_force_size` of the built tree. Defaults to the value used by the
+    /// `new` constructor of the respective tree.
+    pub fn brute_force_size(mut self, brute_force_size: usize) -> Self {
+        self.brute_force_size = Some(brute_force_size);
+        self
+    }
+
+    /// Stores the tight bounding box of every subtree that is larger than the
+    /// `brute_force_size`. The knn and neighbourhood queries use them to prune subtrees
+    /// whose cell is in range but whose points are not, at the cost of two points of
+    /// memory per stored box. The boxes are not kept by serialization.
+    pub fn bounding_boxes(mut self, bounding_boxes: bool) -> Self {
+        self.bounding_boxes = bounding_boxes;
+        self
+    }
+
+    /// Stores a copy of the split points of the top `levels` levels of the tree, at most
+    /// 24, contiguously in breadth first order. In the implicit layout these split points
+    /// are spread across the whole data, so on large trees every query takes a cache miss
+    /// per level. The copy costs one point and one index per node and is not kept by
+    /// serialization. Defaults to 0.
+    pub fn top_levels(mut self, levels: usize) -> Self {
+        self.top_levels = levels;
+        self
+    }
+
+    /// Returns the configuration of a tree with `layout` that was not built by a builder,
+    /// e.g. a deserialized one. The layout does not record the axis policy, so it is
+    /// assumed to be [SplitAxis::MaxSpread] for stored median axes and round robin
+    /// otherwise. Serialized trees store the policy separately, views of the binary
+    /// format keep the assumption.
+    pub(crate) fn for_layout(layout: &SplitLayout, leaf_size: usize) -> Self {
+        let (split_strategy, split_axis) = match layout {
+            SplitLayout::RoundRobin => (SplitStrategy::Median, SplitAxis::RoundRobin),
+            SplitLayout::Axes(_) => (SplitStrategy::Median, SplitAxis::MaxSpread),
+            SplitLayout::BucketLeaf { .. } => (SplitStrategy::BucketLeaf, SplitAxis::RoundRobin),
+            SplitLayout::Explicit { .. } => (SplitStrategy::SlidingMidpoint, SplitAxis::RoundRobin),
+        };
+        Self::new()
+            .split_strategy(split_strategy)
+            .split_axis(split_axis)
+            .leaf_size(leaf_size)
+    }
+
+    /// Builds a [KdTree] taking ownership of `data`.
+    pub fn build<T: Float + Coordinate, const N: usize>(
+        &self,
+        mut data: Vec<[T; N]>,
+    ) -> KdTree<T, N> {
+        let layout = self.layout::<_, T, N>(&mut data, &|pt, axis| pt[axis]);
+        let mut kd_tree = KdTree::from_parts(
+            data,
+            self.brute_force_size
+                .unwrap_or(KdTree::<T, N>::DEFAULT_BRUTE_FORCE_SIZE),
+            layout,
+            self.leaf_size,
+        );
+        kd_tree.builder = *self;
+        kd_tree.rebuild = |builder, data| builder.build(data);
+        if self.bounding_boxes {
+            kd_tree.compute_bounding_boxes();
+        }
+        if self.top_levels > 0 {
+            kd_tree.compute_top_levels(self.top_levels);
+        }
+        kd_tree
+    }
+
+    /// Builds a [KdTree] from the points of `iter`.
+    pub fn build_from_iter<T: Float + Coordinate, const N: usize>(
+        &self,
+        iter: impl IntoIterator<Item = [T; N]>,
+    ) -> KdTree<T, N> {
+        self.build(iter.into_iter().collect())
+    }
+
+    /// Builds a [KdTree] from the points read from `source` chunk by chunk. Fails with the
+    /// first error of `source`.
+    pub fn build_from_chunks<T: Float + Coordinate, const N: usize, S: PointChunks<T, N>>(
+        &self,
+        mut source: S,
+    ) -> Result<KdTree<T, N>, S::Error> {
+        // The hint may come from an untrusted file header.
+        let capacity = prealloc_capacity::<[T; N]>(source.remaining_hint().unwrap_or(0));
+        let mut data = Vec::with_capacity(capacity);
+        while source.read_chunk(&mut data)? > 0 {}
+        data.shrink_to_fit();
+        Ok(self.build(data))
+    }
+
+    /// Builds a [KdTreeMut] by reordering `data` in place.
+    pub fn build_slice_mut<'a, T: Float + Coordinate, const N: usize>(
+        &self,
+        data: &'a mut [[T; N]],
+    ) -> KdTreeMut<'a, T, N> {
+        let layout = self.layout::<_, T, N>(data, &|pt, axis| pt[axis]);
+        let mut kd_tree = GenericKdTree::from_parts(
+            data,
+            self.brute_force_size
+                .unwrap_or(KdTree::<T, N>::DEFAULT_BRUTE_FORCE_SIZE),
+            layout,
+            self.leaf_size,
+        );
+        kd_tree.builder = *self;
+        kd_tree.rebuild = |builder, data| builder.build(data);
+        if self.bounding_boxes {
+            kd_tree.compute_bounding_boxes();
+        }
+        if self.top_levels > 0 {
+            kd_tree.compute_top_levels(self.top_levels);
+        }
+        kd_tree
+    }
+
+    /// Builds a [KdIndexTree] referencing `data`.
+    pub fn build_index<'a, T: Float + Coordinate, const N: usize>(
+        &self,
+        data: &'a [[T; N]],
+    ) -> KdIndexTree<'a, T, N> {
+        self.build_index_unchecked(data)
+    }
+
+    /// Builds a [KdIndexTree] referencing `data` with indices of type `I`. Fails if
+    /// `data` has more points than `I` can address.
+    pub fn try_build_index<'a, T: Float + Coordinate, const N: usize, I: TreeIndex>(
+        &self,
+        data: &'a [[T; N]],
+    ) -> Result<KdIndexTree<'a, T, N, I>, IndexRangeError> {
+        self.try_build_index_from_source(data)
+    }
+
+    /// Builds a [KdIndexTree] over the points of `source`.
+    pub fn build_index_from_source<
+        'a,
+        T: Float + Coordinate,
+        const N: usize,
+        P: PointSource<T, N>,
+    >(
+        &self,
+        source: P,
+    ) -> KdIndexTree<'a, T, N, usize, P> {
+        self.build_index_unchecked(source)
+    }
+
+    /// Builds a [KdIndexTree] over the points of `source` with indices of type `I`. Fails
+    /// if `source` has more points than `I` can address.
+    pub fn try_build_index_from_source<
+        'a,
+        T: Float + Coordinate,
+        const N: usize,
+        I: TreeIndex,
+        P: PointSource<T, N>,
+    >(
+        &self,
+        source: P,
+    ) -> Result<KdIndexTree<'a, T, N, I, P>, IndexRangeError> {
+        IndexRangeError::check::<I>(source.len())?;
+        Ok(self.build_index_unchecked(source))
+    }
+
+    fn build_index_unchecked<
+        'a,
+        T: Float + Coordinate,
+        const N: usize,
+        I: TreeIndex,
+        P: PointSource<T, N>,
+    >(
+        &self,
+        data: P,
+    ) -> KdIndexTree<'a, T, N, I, P> {
+        let mut indices: Vec<_> = (0..data.len()).map(I::from_usize).collect();
+        let layout = self.layout::<_, T, N>(&mut indices, &|index: &I, axis| {
+            data.coord(index.to_usize(), axis)
+        });
+        let mut kd_index_tree = KdIndexTree {
+            indices,
+            data,
+            brute_force_size: self
+                .brute_force_size
+                .unwrap_or(KdIndexTree::<T, N, I, P>::DEFAULT_BRUTE_FORCE_SIZE),
+            layout,
+            leaf_size: self.leaf_size,
+            boxes: BoundingBoxes::none(),
+            top_levels: TopLevels::none(),
+            id: TreeId::new(),
+            _marker: PhantomData,
+        };
+        if self.bounding_boxes {
+            kd_index_tree.compute_bounding_boxes();
+        }
+        if self.top_levels > 0 {
+            kd_index_tree.compute_top_levels(self.top_levels);
+        }
+        kd_index_tree
+    }
+
+    fn layout<E, T: Float, const N: usize>(
+        &self,
+        slice: &mut [E],
+        coordinate: &impl Fn(&E, usize) -> T,
+    ) -> SplitLayout<'static> {
+        let explicit = self.split_strategy != SplitStrategy::Median;
+        let mut axes = if self.split_axis != SplitAxis::RoundRobin || explicit {
+            assert!(N <= 256, "stored split axes support at most 256 dimensions");
+            vec![0; slice.len()]
+        } else {
+            vec![]
+        };
+        if self.split_strategy == SplitStrategy::SlidingMidpoint {
+            assert!(
+                u32::try_from(slice.len()).is_ok(),
+                "sliding midpoint splits support at most u32::MAX points"
+            );
+        }
+        let mut splits = Vec::new();
+        self.build_recursive::<E, T, N>(slice, &mut axes, &mut splits, 0, coordinate);
+
+        match (self.split_strategy, self.split_axis) {
+            (SplitStrategy::Median, SplitAxis::RoundRobin) => SplitLayout::RoundRobin,
+            (SplitStrategy::Median, _) => SplitLayout::Axes(Cow::Owned(axes)),
+            (SplitStrategy::BucketLeaf, _) => SplitLayout::BucketLeaf {
+                axes: Cow::Owned(axes),
+                leaf_size: self.leaf_size,
+            },
+            (SplitStrategy::SlidingMidpoint, _) => SplitLayout::Explicit {
+                axes: Cow::Owned(axes),
+                splits: Cow::Owned(splits),
+            },
+        }
+    }
+
+    /// Reorders `slice` into its subtrees and records the split axes and, for sliding
+    /// midpoint splits, the split positions in pre-order.
+    fn build_recursive<E, T: Float, const N: usize>(
+        &self,
+        slice: &mut [E],
+        axes: &mut [u8],
+        splits: &mut Vec<ExplicitSplit>,
+        row: usize,
+        coordinate: &impl Fn(&E, usize) -> T,
+    ) {
+        let len = slice.len();
+        if len <= self.leaf_size.max(1) {
+            return;
+        }
+
+        let row = match self.split_axis {
+            SplitAxis::RoundRobin => row,
+            split_axis => split_axis.select::<E, T, N>(slice, coordinate),
+        };
+        let compare = |lhs: &E, rhs: &E| {
+            coordinate(lhs, row)
+                .partial_cmp(&coordinate(rhs, row))
+                .unwrap()
+        };
+
+        let split_index = match self.split_strategy {
+            SplitStrategy::Median => len / 2,
+            SplitStrategy::BucketLeaf => bucket_leaf_split_index(len, self.leaf_size),
+            SplitStrategy::SlidingMidpoint => {
+                Self::sliding_midpoint_split_index(slice, row, coordinate)
+            }
+        };
+        if self.split_strategy == SplitStrategy::SlidingMidpoint {
+            // The points below the midpoint are already in front of the split index,
+            // the split point is the smallest of the remaining points.
+            slice[split_index..].select_nth_unstable_by(0, compare);
+        } else {
+            slice.select_nth_unstable_by(split_index, compare);
+        }
+        let (slice1, slice2) = slice.split_at_mut(split_index);
+
+        if !axes.is_empty() {
+            axes[split_index] = row as u8;
+        }
+        let node = splits.len();
+        let explicit = self.split_strategy == SplitStrategy::SlidingMidpoint;
+        if explicit {
+            splits.push(ExplicitSplit {
+                split_index: split_index as u32,
+                left_splits: 0,
+            });
+        }
+
+        let (axes1, axes2) = split_around(axes, split_index);
+
+        let row = (row + 1) % N;
+        self.build_recursive::<E, T, N>(slice1, axes1, splits, row, coordinate);
+        if explicit {
+            splits[node].left_splits = (splits.len() - node - 1) as u32;
+        }
+        self.build_recursive::<E, T, N>(&mut slice2[1..], axes2, splits, row, coordinate);
+    }
+
+    /// Moves all points below the midpoint of the extent along `row` to the front and
+    /// returns their number, which is the position of the split point. Falls back to
+    /// the median if all points share the same coordinate. If rounding puts all points on
+    /// one side, the split slides to the closest point, so that both sides are non-empty.
+    fn sliding_midpoint_split_index<E, T: Float>(
+        slice: &mut [E],
+        row: usize,
+        coordinate: &impl Fn(&E, usize) -> T,
+    ) -> usize {
+        let (min, max) = slice
+            .iter()
+            .map(|e| coordinate(e, row))
+            .fold((T::infinity(), T::neg_infinity()), |(min, max), x| {
+                (min.min(x), max.max(x))
+            });
+        if min >= max {
+            return slice.len() / 2;
+        }
+
+        // The sum and the extent of large coordinates may overflow, their halves do not.
+        let two = T::one() + T::one();
+        let half_extent = (max - min) / two;
+        let midpoint = if half_extent.is_finite() {
+            min + half_extent
+        } else {
+            min / two + max / two
+        };
+        let mut below = 0;
+        for i in 0..slice.len() {
+            if coordinate(&slice[i], row) < midpoint {
+                slice.swap(below, i);
+                below += 1;
+            }
+        }
+
+        let compare = |lhs: &&E, rhs: &&E| {
+            coordinate(lhs, row)
+                .partial_cmp(&coordinate(rhs, row))
+                .unwrap()
+        };
+        if below == 0 {
+            let (position, _) = slice
+                .iter()
+                .enumerate()
+                .min_by(|a, b| compare(&a.1, &b.1))
+                .unwrap();
+            slice.swap(0, position);
+            below = 1;
+        } else if below == slice.len() {
+            let (position, _) = slice
+                .iter()
+                .enumerate()
+                .max_by(|a, b| compare(&a.1, &b.1))
+                .unwrap();
+            slice.swap(slice.len() - 1, position);
+            below = slice.len() - 1;
+        }
+        below
+    }
+}
+
+/// Splits a per-point slice of a subtree into the slices of both child subtrees. Empty
+/// slices, used for information that is not stored, stay empty.
+fn split_around<E>(slice: &mut [E], split_index: usize) -> (&mut [E], &mut [E]) {
+    if slice.is_empty() {
+        return (&mut [], &mut []);
+    }
+    let (slice1, slice2) = slice.split_at_mut(split_index);
+    (slice1, &mut slice2[1..])
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+
+    use super::{KdTreeBuilder, SplitStrategy};
+    use crate::{distance, scattered_points, split::SplitLayout, Boundary, SplitAxis};
+
+    #[test]
+    fn builder_test() {
+        let mut data = vec![];
+        for x in 0..20 {
+            for y in 0..10 {
+                data.push([x as f64 * 0.1, y as f64 * 5.0]);
+            }
+        }
+        // A dense cluster of duplicates.
+        data.extend([[0.5, 20.0]; 30]);
+
+        for split_strategy in [
+            SplitStrategy::Median,
+            SplitStrategy::SlidingMidpoint,
+            SplitStrategy::BucketLeaf,
+        ] {
+            for split_axis in [SplitAxis::RoundRobin, SplitAxis::MaxSpread] {
+                for leaf_size in [1, 6] {
+                    let builder = KdTreeBuilder::new()
+                        .split_strategy(split_strategy)
+                        .split_axis(split_axis)
+                        .leaf_size(leaf_size)
+                        .brute_force_size(0);
+                    let kd_tree = builder.build(data.clone());
+                    let kd_index_tree = builder.build_index(&data);
+                    assert_eq!(kd_tree.validate(), Ok(()));
+
+                    // Only sliding midpoint splits store their positions, one per subtree
+                    // larger than a leaf.
+                    match &kd_tree.layout {
+                        SplitLayout::Explicit { splits, .. } => {
+                            assert_eq!(split_strategy, SplitStrategy::SlidingMidpoint);
+                            assert!(
+                                !splits.is_empty() && splits.len() * leaf_size < 2 * data.len()
+                            );
+                        }
+                        SplitLayout::BucketLeaf { .. } => {
+                            assert_eq!(split_strategy, SplitStrategy::BucketLeaf)
+                        }
+                        _ => assert_eq!(split_strategy, SplitStrategy::Median),
+                    }
+
+                    for point in [[0.0, 0.0], [0.52, 19.0], [3.0, 60.0]] {
+                        let mut expected: Vec<_> =
+                            data.iter().map(|pt| distance(pt, &point)).collect();
+                        expected.sort_by(|lhs, rhs| lhs.partial_cmp(rhs).unwrap());
+
+                        let knn: Vec<_> = kd_tree.knn(&point, 5).iter().map(|(d, _)| *d).collect();
+                        assert_eq!(knn, expected[..5]);
+                        let knn: Vec<_> = kd_index_tree
+                            .knn_by_index(&point, 5)
+                            .iter()
+                            .map(|(d, _)| *d)
+                            .collect();
+                        assert_eq!(knn, expected[..5]);
+
+                        let eps = 5.0;
+                        let count = expected.iter().filter(|dst| **dst <= eps).count();
+                        assert_eq!(
+                            kd_tree.neighbourhood(&point, eps, Boundary::Closed).len(),
+                            count
+                        );
+                        assert_eq!(
+                            kd_index_tree.count_neighbourhood(&point, eps, Boundary::Closed),
+                            count
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sliding_midpoint_large_coordinates_test() {
+        // The sum of the extremes overflows.
+        let data: Vec<[f64; 2]> = (0..40)
+            .map(|i| {
+                [
+                    f64::MAX - (i as f64) * 1e300,
+                    -f64::MAX + (i % 7) as f64 * 1e300,
+                ]
+            })
+            .chain([[f64::MAX, f64::MAX], [-f64::MAX, -f64::MAX]])
+            .collect();
+        let builder = KdTreeBuilder::new()
+            .split_strategy(SplitStrategy::SlidingMidpoint)
+            .brute_force_size(0);
+        let kd_tree = builder.build(data.clone());
+        assert_eq!(kd_tree.validate(), Ok(()));
+        assert_eq!(kd_tree.nearest(&data[10]).unwrap().1, &data[10]);
+
+        #[cfg(feature = "half")]
+        {
+            use half::f16;
+
+            let data: Vec<[f16; 2]> = (0..40)
+                .map(|i| {
+                    [
+                        f16::from_f32(40000.0 + i as f32 * 500.0),
+                        f16::from_f32(i as f32),
+                    ]
+                })
+                .collect();
+            let kd_tree = builder.build(data.clone());
+            assert_eq!(kd_tree.validate(), Ok(()));
+            assert_eq!(kd_tree.nearest(&data[10]).unwrap().1, &data[10]);
+        }
+    }
+
+    #[test]
+    fn bounding_boxes_test() {
+        // Two distant clusters, so that the cells of many subtrees are much larger than
+        // their points.
+        let data: Vec<_> = scattered_points(600)
+            .iter()
+            .enumerate()
+            .map(|(i, [x, y])| {
+                let offset = if i % 2 == 0 { 0.0 } else { 100.0 };
+                [offset + x * 0.1, y * 0.2]
+            })
+            .collect();
+
+        for split_strategy in [SplitStrategy::Median, SplitStrategy::SlidingMidpoint] {
+            let builder = KdTreeBuilder::new()
+                .split_strategy(split_strategy)
+                .leaf_size(4)
+                .brute_force_size(8);
+            let kd_tree = builder.build(data.clone());
+            let boxed_kd_tree = builder.bounding_boxes(true).build(data.clone());
+            let kd_index_tree = builder.build_index(&data);
+            let boxed_kd_index_tree = builder.bounding_boxes(true).build_index(&data);
+
+            let root_bounds = boxed_kd_tree.boxes.node_boxes().bounds(data.len()).copied();
+            assert_eq!(root_bounds, Some([[0.0, 0.0], [110.0, 96.0 * 0.1]]));
+            assert!(boxed_kd_tree
+                .view()
+                .boxes
+                .node_boxes()
+                .bounds(data.len())
+                .is_some());
+
+            for point in [[0.0, 0.0], [50.0, 5.0], [103.0, 4.0], [200.0, -10.0]] {
+                for k in [1, 7, 20] {
+                    assert_eq!(boxed_kd_tree.knn(&point, k), kd_tree.knn(&point, k));
+                    assert_eq!(
+                        boxed_kd_index_tree.knn_by_index(&point, k),
+                        kd_index_tree.knn_by_index(&point, k)
+                    );
+                }
+                for eps in [0.5, 3.0, 60.0] {
+                    assert_eq!(
+                        boxed_kd_tree.neighbourhood_by_index(&point, eps, Boundary::Closed),
+                        kd_tree.neighbourhood_by_index(&point, eps, Boundary::Closed)
+                    );
+                    assert_eq!(
+                        boxed_kd_index_tree.count_neighbourhood(&point, eps, Boundary::Open),
+                        kd_index_tree.count_neighbourhood(&point, eps, Boundary::Open)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn top_levels_test() {
+        let data: Vec<_> = (0..1000)
+            .map(|i| {
+                [
+                    (i * 37 % 101) as f64 * 0.1,
+                    (i * 53 % 97) as f64 * 0.1,
+                    (i * 29 % 89) as f64 * 0.1,
+                ]
+            })
+            .collect();
+
+        for split_strategy in [SplitStrategy::Median, SplitStrategy::SlidingMidpoint] {
+            for split_axis in [SplitAxis::RoundRobin, SplitAxis::MaxSpread] {
+                let builder = KdTreeBuilder::new()
+                    .split_strategy(split_strategy)
+                    .split_axis(split_axis)
+                    .leaf_size(2)
+                    .brute_force_size(1);
+                let kd_tree = builder.build(data.clone());
+                let kd_index_tree = builder.build_index(&data);
+                for levels in [1, 4, 12, 40] {
+                    let top_kd_tree = builder.top_levels(levels).build(data.clone());
+                    let top_kd_index_tree = builder.top_levels(levels).build_index(&data);
+
+                    for point in [[0.0, 0.0, 0.0], [5.0, 4.0, 3.0], [20.0, -1.0, 4.0]] {
+                        let knn = top_kd_tree.knn(&point, 10);
+                        assert_eq!(knn, kd_tree.knn(&point, 10));
+                        // Results reference the data, not the copied split points.
+                        assert!(knn.iter().all(|(_, pt)| top_kd_tree
+                            .data()
+                            .as_ptr_range()
+                            .contains(&(*pt as *const _))));
+                        assert_eq!(top_kd_tree.view().knn(&point, 10), knn);
+                        assert_eq!(top_kd_tree.nearest(&point), kd_tree.nearest(&point));
+                        assert_eq!(
+                            top_kd_tree.k_furthest_by_index(&point, 5),
+                            kd_tree.k_furthest_by_index(&point, 5)
+                        );
+                        assert_eq!(
+                            top_kd_tree.shell_by_index(&point, 1.0, 2.0, Boundary::Closed),
+                            kd_tree.shell_by_index(&point, 1.0, 2.0, Boundary::Closed)
+                        );
+                        assert_eq!(
+                            top_kd_tree.neighbourhood(&point, 1.5, Boundary::Closed),
+                            kd_tree.neighbourhood(&point, 1.5, Boundary::Closed)
+                        );
+                        assert_eq!(
+                            top_kd_index_tree.knn_by_index(&point, 10),
+                            kd_index_tree.knn_by_index(&point, 10)
+                        );
+                        assert_eq!(
+                            top_kd_index_tree.neighbourhood_by_index(&point, 1.5, Boundary::Open),
+                            kd_index_tree.neighbourhood_by_index(&point, 1.5, Boundary::Open)
+                        );
+                        assert_eq!(
+                            top_kd_index_tree.count_shell(&point, 1.0, 2.0, Boundary::Open),
+                            kd_index_tree.count_shell(&point, 1.0, 2.0, Boundary::Open)
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn build_from_chunks_test() {
+        use crate::{PointChunks, PointReader};
+
+        let data: Vec<[f32; 3]> = (0..100)
+            .map(|i| [i as f32, (i * 7 % 13) as f32, (i * 3 % 11) as f32])
+            .collect();
+        let bytes: Vec<u8> = data
+            .iter()
+            .flatten()
+            .flat_map(|coordinate| coordinate.to_le_bytes())
+            .collect();
+
+        let builder = KdTreeBuilder::new().leaf_size(4);
+        let kd_tree = builder.build(data.clone());
+        for chunk_len in [1, 7, 100, 1000] {
+            let reader = PointReader::<_, f32, 3>::new(&bytes[..]).chunk_len(chunk_len);
+            assert_eq!(
+                builder.build_from_chunks(reader).unwrap().data(),
+                kd_tree.data()
+            );
+        }
+        assert_eq!(builder.build_from_iter(data.clone()).data(), kd_tree.data());
+
+        let mut reader = PointReader::<_, f32, 3>::new(&bytes[..])
+            .chunk_len(60)
+            .expected_len(100);
+        let mut points = vec![];
+        assert_eq!(reader.read_chunk(&mut points).unwrap(), 60);
+        assert_eq!(reader.remaining_hint(), Some(40));
+        assert_eq!(reader.read_chunk(&mut points).unwrap(), 40);
+        assert_eq!(reader.read_chunk(&mut points).unwrap(), 0);
+        assert_eq!(points, data);
+
+        // A bogus expected length does not allocate up front.
+        let reader = PointReader::<_, f32, 3>::new(&bytes[..]).expected_len(usize::MAX / 2);
+        assert_eq!(builder.build_from_chunks(reader).unwrap().len(), 100);
+
+        let truncated = PointReader::<_, f32, 3>::new(&bytes[..bytes.len() - 1]);
+        match builder.build_from_chunks(truncated) {
+            Err(error) => assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof),
+            Ok(_) => panic!("a truncated point was read"),
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use core::fmt::Debug;
+use num_traits::Zero;
+
+/// Scalar type of the point coordinates.
+///
+/// For `f32` and `f64` the queries take and return Euclidean distances of the same type.
+/// For integer coordinates they take and return exact squared Euclidean distances in a
+/// wider accumulator type, e.g. `i64` for `i32` coordinates, so radii have to be given
+/// squared as well. Squared distances that exceed the accumulator saturate at its
+/// maximum.
+pub trait Coordinate: Copy + PartialOrd + Debug {
+    /// Type of the distances taken and returned by the queries.
+    type Distance: Copy + PartialOrd + Debug + Zero;
+
+    /// Lower bound of all coordinates.
+    const MIN: Self;
+
+    /// Upper bound of all coordinates.
+    const MAX: Self;
+
+    /// A distance that is not smaller than the distance between any two points.
+    const MAX_DISTANCE: Self::Distance;
+
+    /// Offset between `self` and `other` along one axis. Only its magnitude is used.
+    fn offset(self, other: Self) -> Self::Distance;
+
+    /// Adds the square of `offset` to the sum of squares `sum`.
+    fn add_square(sum: Self::Distance, offset: Self::Distance) -> Self::Distance;
+
+    /// Distance corresponding to the sum of the squared per axis offsets.
+    fn from_sum_of_squares(sum: Self::Distance) -> Self::Distance;
+
+    /// Distance corresponding to the per axis `offsets`.
+    #[inline]
+    fn norm(offsets: &[Self::Distance]) -> Self::Distance {
+        Self::from_sum_of_squares(offsets.iter().fold(Self::Distance::zero(), |sum, offset| {
+            Self::add_square(sum, *offset)
+        }))
+    }
+
+    /// Calls `f` with every position in `0..len` and the distance of `leaf_point(position)`
+    /// to `point`, as done by the brute force leaf scans. With the `simd` feature `f32` and
+    /// `f64` compute the distances of several points at once, with identical results.
+    #[inline]
+    fn leaf_distances<const N: usize>(
+        point: &[Self; N],
+        len: usize,
+        leaf_point: impl Fn(usize) -> [Self; N],
+        mut f: impl FnMut(usize, Self::Distance),
+    ) {
+        for position in 0..len {
+            f(position, crate::distance(point, &leaf_point(position)));
+        }
+    }
+
+    /// Like [Self::leaf_distances] for the flat points of a [crate::DynKdTree], which
+    /// consist of `point.len()` consecutive coordinates of `leaf` each.
+    #[inline]
+    fn slice_leaf_distances(
+        point: &[Self],
+        leaf: &[Self],
+        mut f: impl FnMut(usize, Self::Distance),
+    ) {
+        for (position, leaf_point) in leaf.chunks_exact(point.len()).enumerate() {
+            f(position, crate::slice_distance(point, leaf_point));
+        }
+    }
+}
+
+macro_rules! impl_coordinate_for_float {
+    ($($float:ty => $leaf_distances:ident, $slice_leaf_distances:ident),+) => {$(
+        impl Coordinate for $float {
+            type Distance = $float;
+
+            const MIN: Self = <$float>::NEG_INFINITY;
+            const MAX: Self = <$float>::INFINITY;
+            const MAX_DISTANCE: Self::Distance = <$float>::INFINITY;
+
+            #[inline]
+            fn offset(self, other: Self) -> Self::Distance {
+                self - other
+            }
+
+            #[inline]
+            fn add_square(sum: Self::Distance, offset: Self::Distance) -> Self::Distance {
+                sum + offset * offset
+            }
+
+            #[inline]
+            fn from_sum_of_squares(sum: Self::Distance) -> Self::Distance {
+                sum.sqrt()
+            }
+
+            #[cfg(feature = "simd")]
+            #[inline]
+            fn leaf_distances<const N: usize>(
+                point: &[Self; N],
+                len: usize,
+                leaf_point: impl Fn(usize) -> [Self; N],
+                f: impl FnMut(usize, Self::Distance),
+            ) {
+                crate::simd::$leaf_distances(point, len, leaf_point, f)
+            }
+
+            #[cfg(feature = "simd")]
+            #[inline]
+            fn slice_leaf_distances(
+                point: &[Self],
+                leaf: &[Self],
+                f: impl FnMut(usize, Self::Distance),
+            ) {
+                crate::simd::$slice_leaf_distances(point, leaf, f)
+            }
+        }
+    )+};
+}
+
+impl_coordinate_for_float!(
+    f32 => leaf_distances_f32, slice_leaf_distances_f32,
+    f64 => leaf_distances_f64, slice_leaf_distances_f64
+);
+
+macro_rules! impl_coordinate_for_integer {
+    ($($integer:ty => $accumulator:ty),+) => {$(
+        impl Coordinate for $integer {
+            type Distance = $accumulator;
+
+            const MIN: Self = <$integer>::MIN;
+            const MAX: Self = <$integer>::MAX;
+            const MAX_DISTANCE: Self::Distance = <$accumulator>::MAX;
+
+            #[inline]
+            fn offset(self, other: Self) -> Self::Distance {
+                self.abs_diff(other) as $accumulator
+            }
+
+            #[inline]
+            fn add_square(sum: Self::Distance, offset: Self::Distance) -> Self::Distance {
+                sum.saturating_add(offset.saturating_mul(offset))
+            }
+
+            #[inline]
+            fn from_sum_of_squares(sum: Self::Distance) -> Self::Distance {
+                sum
+            }
+        }
+    )+};
+}
+
+impl_coordinate_for_integer!(i16 => i64, i32 => i64, i64 => i128, u16 => u64, u32 => u64);
+
+// Half precision coordinates halve the memory of the points. Distances are computed and
+// returned as `f32`.
+#[cfg(feature = "half")]
+macro_rules! impl_coordinate_for_half {
+    ($($half:ty),+) => {$(
+        impl Coordinate for $half {
+            type Distance = f32;
+
+            const MIN: Self = <$half>::NEG_INFINITY;
+            const MAX: Self = <$half>::INFINITY;
+            const MAX_DISTANCE: Self::Distance = f32::INFINITY;
+
+            #[inline]
+            fn offset(self, other: Self) -> Self::Distance {
+                self.to_f32() - other.to_f32()
+            }
+
+            #[inline]
+            fn add_square(sum: Self::Distance, offset: Self::Distance) -> Self::Distance {
+                f32::add_square(sum, offset)
+            }
+
+            #[inline]
+            fn from_sum_of_squares(sum: Self::Distance) -> Self::Distance {
+                f32::from_sum_of_squares(sum)
+            }
+        }
+    )+};
+}
+
+#[cfg(feature = "half")]
+impl_coordinate_for_half!(half::f16, half::bf16);
@@ -0,0 +1,109 @@
+use super::DynKdTree;
+use crate::{query_stats, slice_distance, Boundary, Coordinate, DynNeighbourhoodParams};
+use alloc::vec;
+use num_traits::Zero;
+
+impl<T: Coordinate> DynKdTree<T> {
+    pub fn count_neighbourhood(
+        &self,
+        point: &[T],
+        epsilon: T::Distance,
+        boundary: Boundary,
+    ) -> usize {
+        self.check_dim(point);
+        let mut subtree_distance = vec![T::Distance::zero(); self.dim];
+        let params = DynNeighbourhoodParams {
+            point,
+            epsilon,
+            boundary,
+            brute_force_size: self.brute_force_size,
+        };
+
+        self.count_neighbourhood_recursive(&self.data, &params, &mut subtree_distance, 0)
+    }
+
+    #[inline]
+    fn dispatch_count_neighbourhood_recursive_on_subtrees(
+        &self,
+        subtree1: &[T],
+        subtree2: &[T],
+        split_point: &[T],
+        params: &DynNeighbourhoodParams<T>,
+        subtree_distance: &mut [T::Distance],
+        row: usize,
+    ) -> usize {
+        let next_row = (row + 1) % self.dim;
+        let mut result = 0;
+        result += self.count_neighbourhood_recursive(subtree1, params, subtree_distance, next_row);
+
+        let row_value = subtree_distance[row];
+        subtree_distance[row] = params.point[row].offset(split_point[row]);
+        if params
+            .boundary
+            .contains(T::norm(subtree_distance), params.epsilon)
+        {
+            result +=
+                self.count_neighbourhood_recursive(subtree2, params, subtree_distance, next_row);
+        } else {
+            query_stats::subtree_pruned();
+        }
+        subtree_distance[row] = row_value;
+
+        result
+    }
+
+    fn count_neighbourhood_recursive(
+        &self,
+        subtree: &[T],
+        params: &DynNeighbourhoodParams<T>,
+        subtree_distance: &mut [T::Distance],
+        row: usize,
+    ) -> usize {
+        let _node = query_stats::enter_node();
+        let len = subtree.len() / self.dim;
+        if len <= params.brute_force_size.max(1) {
+            query_stats::leaf_scan();
+            let mut count = 0;
+            T::slice_leaf_distances(params.point, subtree, |_, dst| {
+                if params.boundary.contains(dst, params.epsilon) {
+                    count += 1;
+                }
+            });
+            count
+        } else {
+            let split_index = len / 2;
+            let split_point = &subtree[split_index * self.dim..(split_index + 1) * self.dim];
+
+            let mut result = 0;
+            if params
+                .boundary
+                .contains(slice_distance(split_point, params.point), params.epsilon)
+            {
+                result += 1;
+            }
+
+            let subtree1 = &subtree[..split_index * self.dim];
+            let subtree2 = &subtree[(split_index + 1) * self.dim..];
+            if params.point[row] <= split_point[row] {
+                result += self.dispatch_count_neighbourhood_recursive_on_subtrees(
+                    subtree1,
+                    subtree2,
+                    split_point,
+                    params,
+                    subtree_distance,
+                    row,
+                );
+            } else if params.point[row] > split_point[row] {
+                result += self.dispatch_count_neighbourhood_recursive_on_subtrees(
+                    subtree2,
+                    subtree1,
+                    split_point,
+                    params,
+                    subtree_distance,
+                    row,
+                );
+            }
+            result
+        }
+    }
+}
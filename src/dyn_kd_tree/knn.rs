@@ -0,0 +1,13 @@
+use super::DynKdTree;
+use crate::Coordinate;
+use alloc::vec::Vec;
+
+impl<T: Coordinate> DynKdTree<T> {
+    /// Returns the distance and a reference to the k nearest points, sorted by distance.
+    pub fn knn(&self, point: &[T], k: usize) -> Vec<(T::Distance, &[T])> {
+        self.knn_by_index(point, k)
+            .into_iter()
+            .map(|(dst, index)| (dst, self.point(index)))
+            .collect()
+    }
+}
@@ -0,0 +1,145 @@
+use super::DynKdTree;
+use crate::{query_stats, slice_distance, Coordinate, DynKnnParams};
+use alloc::{vec, vec::Vec};
+use num_traits::Zero;
+
+impl<T: Coordinate> DynKdTree<T> {
+    /// Returns the distance and the index of the k nearest points, see [Self::point],
+    /// sorted by distance.
+    pub fn knn_by_index(&self, point: &[T], k: usize) -> Vec<(T::Distance, usize)> {
+        self.check_dim(point);
+        if k == 0 {
+            return vec![];
+        }
+        let mut subtree_distance = vec![T::Distance::zero(); self.dim];
+        let mut result = Vec::with_capacity(k);
+
+        let params = DynKnnParams {
+            point,
+            k: core::num::NonZero::new(k).unwrap(),
+            brute_force_size: self.brute_force_size,
+        };
+        self.find_knn_by_index_recursive(
+            0,
+            &self.data,
+            &params,
+            &mut subtree_distance,
+            &mut result,
+            0,
+        );
+
+        result
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[inline]
+    fn dispatch_find_knn_by_index_recursive_on_subtrees(
+        &self,
+        subtree1_offset: usize,
+        subtree1: &[T],
+        subtree2_offset: usize,
+        subtree2: &[T],
+        split_point: &[T],
+        params: &DynKnnParams<T>,
+        subtree_distance: &mut [T::Distance],
+        result: &mut Vec<(T::Distance, usize)>,
+        row: usize,
+    ) {
+        let next_row = (row + 1) % self.dim;
+        self.find_knn_by_index_recursive(
+            subtree1_offset,
+            subtree1,
+            params,
+            subtree_distance,
+            result,
+            next_row,
+        );
+
+        let row_value = subtree_distance[row];
+        subtree_distance[row] = params.point[row].offset(split_point[row]);
+        let dst = T::norm(subtree_distance);
+        if result.len() < params.k.get() || dst < result.last().unwrap().0 {
+            self.find_knn_by_index_recursive(
+                subtree2_offset,
+                subtree2,
+                params,
+                subtree_distance,
+                result,
+                next_row,
+            );
+        } else {
+            query_stats::subtree_pruned();
+        }
+        subtree_distance[row] = row_value;
+    }
+
+    fn find_knn_by_index_recursive(
+        &self,
+        subtree_offset: usize,
+        subtree: &[T],
+        params: &DynKnnParams<T>,
+        subtree_distance: &mut [T::Distance],
+        result: &mut Vec<(T::Distance, usize)>,
+        row: usize,
+    ) {
+        let _node = query_stats::enter_node();
+        let len = subtree.len() / self.dim;
+        if len <= params.brute_force_size.max(1) {
+            query_stats::leaf_scan();
+            T::slice_leaf_distances(params.point, subtree, |position, dst| {
+                Self::knn_try_insert_index(params, result, dst, subtree_offset + position)
+            });
+        } else {
+            let split_index = len / 2;
+            let split_point = &subtree[split_index * self.dim..(split_index + 1) * self.dim];
+
+            let subtree1 = &subtree[..split_index * self.dim];
+            let subtree2 = &subtree[(split_index + 1) * self.dim..];
+            if params.point[row] <= split_point[row] {
+                self.dispatch_find_knn_by_index_recursive_on_subtrees(
+                    subtree_offset,
+                    subtree1,
+                    subtree_offset + split_index + 1,
+                    subtree2,
+                    split_point,
+                    params,
+                    subtree_distance,
+                    result,
+                    row,
+                );
+            } else if params.point[row] > split_point[row] {
+                self.dispatch_find_knn_by_index_recursive_on_subtrees(
+                    subtree_offset + split_index + 1,
+                    subtree2,
+                    subtree_offset,
+                    subtree1,
+                    split_point,
+                    params,
+                    subtree_distance,
+                    result,
+                    row,
+                );
+            }
+
+            let dst = slice_distance(split_point, params.point);
+            Self::knn_try_insert_index(params, result, dst, subtree_offset + split_index);
+        }
+    }
+
+    #[inline]
+    fn knn_try_insert_index(
+        params: &DynKnnParams<T>,
+        result: &mut Vec<(T::Distance, usize)>,
+        dst: T::Distance,
+        index: usize,
+    ) {
+        if result.len() < params.k.get() {
+            let pos = result.partition_point(|p| p.0 <= dst);
+            result.insert(pos, (dst, index));
+        } else if dst < result.last().unwrap().0 {
+            let pos = result.partition_point(|p| p.0 <= dst);
+            result.insert(pos, (dst, index));
+            let _ = result.pop();
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use alloc::vec::Vec;
+
+use crate::Coordinate;
+
+pub mod count_neighbourhood;
+pub mod knn;
+pub mod knn_by_index;
+pub mod neighbourhood;
+pub mod neighbourhood_by_index;
+
+/// A K-d tree whose dimension is chosen at runtime, e.g. by a CLI tool or a language
+/// binding. The points are stored flat, `dim` coordinates per point. Prefer a
+/// [crate::KdTree] if the dimension is known at compile time.
+#[derive(Debug, Clone)]
+pub struct DynKdTree<T> {
+    data: Vec<T>,
+    dim: usize,
+    pub brute_force_size: usize,
+}
+
+impl<T: Coordinate> DynKdTree<T> {
+    /// Same heuristic as [crate::KdTree::DEFAULT_BRUTE_FORCE_SIZE].
+    pub const DEFAULT_BRUTE_FORCE_SIZE: usize = if core::mem::size_of::<T>() >= 64 {
+        25
+    } else {
+        34
+    };
+
+    /// Create a new K-d Tree over the points of `data`, each consisting of `dim`
+    /// consecutive coordinates. The points are reordered in place, no copy of `data` is
+    /// made. Panics if `dim` is zero or does not divide the length of `data`.
+    pub fn new(mut data: Vec<T>, dim: usize) -> Self {
+        assert!(dim > 0, "the dimension must not be zero");
+        assert_eq!(
+            data.len() % dim,
+            0,
+            "the data length must be a multiple of the dimension"
+        );
+
+        let mut indices: Vec<_> = (0..data.len() / dim).collect();
+        if !indices.is_empty() {
+            Self::select_median_with_row_recursive(&mut indices, &data, dim, 0);
+        }
+        // Move the points into tree order by following the cycles of the permutation.
+        // Placed points are marked by pointing to their own position.
+        for start in 0..indices.len() {
+            let mut position = start;
+            loop {
+                let source = indices[position];
+                indices[position] = position;
+                if source == start {
+                    break;
+                }
+                for axis in 0..dim {
+                    data.swap(position * dim + axis, source * dim + axis);
+                }
+                position = source;
+            }
+        }
+
+        Self {
+            data,
+            dim,
+            brute_force_size: Self::DEFAULT_BRUTE_FORCE_SIZE,
+        }
+    }
+
+    /// Create a new K-d Tree and sets the `brute_force_size`.
+    pub fn with_brute_force_size(data: Vec<T>, dim: usize, brute_force_size: usize) -> Self {
+        let mut self_ = Self::new(data, dim);
+        self_.brute_force_size = brute_force_size;
+        self_
+    }
+
+    fn select_median_with_row_recursive(slice: &mut [usize], data: &[T], dim: usize, row: usize) {
+        let split_index = slice.len() / 2;
+        slice.select_nth_unstable_by(split_index, |lhs, rhs| {
+            data[lhs * dim + row]
+                .partial_cmp(&data[rhs * dim + row])
+                .unwrap()
+        });
+
+        if slice.len() > 3 {
+            let (slice1, slice2) = slice.split_at_mut(split_index);
+
+            let row = (row + 1) % dim;
+            if slice1.len() > 1 {
+                Self::select_median_with_row_recursive(slice1, data, dim, row);
+            }
+
+            if slice2.len() > 2 {
+                let slice2 = &mut slice2[1..];
+                Self::select_median_with_row_recursive(slice2, data, dim, row);
+            }
+        }
+    }
+
+    /// Dimension of the points.
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// Number of points in the DynKdTree.
+    pub fn len(&self) -> usize {
+        self.data.len() / self.dim
+    }
+
+    /// Returns true if the DynKdTree is empty.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns a read-only reference to the flat data in tree order.
+    pub fn data(&self) -> &[T] {
+        &self.data
+    }
+
+    /// Returns the point at `index` of the tree order, as returned by the `_by_index`
+    /// queries.
+    pub fn point(&self, index: usize) -> &[T] {
+        &self.data[index * self.dim..(index + 1) * self.dim]
+    }
+
+    fn check_dim(&self, point: &[T]) {
+        assert_eq!(
+            point.len(),
+            self.dim,
+            "the query point must have the dimension of the tree"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+
+    use super::DynKdTree;
+    use crate::{Boundary, KdTree};
+
+    #[test]
+    fn dyn_kd_tree_test() {
+        let points: Vec<[f64; 5]> = (0..500)
+            .map(|i| {
+                [
+                    (i * 37 % 101) as f64,
+                    (i * 53 % 97) as f64 * 0.5,
+                    (i % 7) as f64,
+                    (i * 11 % 13) as f64,
+                    -(i as f64),
+                ]
+            })
+            .collect();
+        let kd_tree = KdTree::new(points.clone());
+        let dyn_kd_tree = DynKdTree::new(points.concat(), 5);
+        assert_eq!(dyn_kd_tree.len(), 500);
+        assert_eq!(dyn_kd_tree.data(), kd_tree.data().concat());
+
+        for point in [[0.0; 5], [50.0, 20.0, 3.0, 6.0, -250.0]] {
+            let knn = dyn_kd_tree.knn(&point, 10);
+            let expected = kd_tree.knn(&point, 10);
+            assert_eq!(knn.len(), expected.len());
+            for ((dst, pt), (expected_dst, expected_pt)) in knn.iter().zip(&expected) {
+                assert_eq!(dst, expected_dst);
+                assert_eq!(*pt, &expected_pt[..]);
+            }
+
+            for epsilon in [0.0, 5.0, 40.0] {
+                let mut neighbourhood =
+                    dyn_kd_tree.neighbourhood_by_index(&point, epsilon, Boundary::Closed);
+                neighbourhood.sort();
+                let mut expected =
+                    kd_tree.neighbourhood_by_index(&point, epsilon, Boundary::Closed);
+                expected.sort();
+                assert_eq!(neighbourhood, expected);
+                assert_eq!(
+                    dyn_kd_tree.count_neighbourhood(&point, epsilon, Boundary::Open),
+                    kd_tree.count_neighbourhood(&point, epsilon, Boundary::Open)
+                );
+            }
+        }
+
+        let dyn_kd_tree = DynKdTree::new(vec![3i32, 1, 4, 1, 5, 9, 2, 6, 5], 1);
+        assert_eq!(
+            dyn_kd_tree.knn(&[0], 3),
+            vec![(1, &[1][..]), (1, &[1][..]), (4, &[2][..])]
+        );
+        assert!(DynKdTree::<f32>::new(vec![], 3)
+            .knn(&[0.0; 3], 1)
+            .is_empty());
+    }
+}
@@ -0,0 +1,19 @@
+use super::DynKdTree;
+use crate::{Boundary, Coordinate};
+use alloc::vec::Vec;
+
+impl<T: Coordinate> DynKdTree<T> {
+    /// Returns a list of references to points within the ball of radius epsilon around p.
+    /// `boundary` determines whether points at a distance of exactly epsilon are included.
+    pub fn neighbourhood(
+        &self,
+        point: &[T],
+        epsilon: T::Distance,
+        boundary: Boundary,
+    ) -> Vec<&[T]> {
+        self.neighbourhood_by_index(point, epsilon, boundary)
+            .into_iter()
+            .map(|index| self.point(index))
+            .collect()
+    }
+}
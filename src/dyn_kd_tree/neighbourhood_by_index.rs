@@ -0,0 +1,140 @@
+use super::DynKdTree;
+use crate::{query_stats, slice_distance, Boundary, Coordinate, DynNeighbourhoodParams};
+use alloc::{vec, vec::Vec};
+use num_traits::Zero;
+
+impl<T: Coordinate> DynKdTree<T> {
+    /// Returns the index of all points within the ball of radius epsilon around p, see
+    /// [Self::point]. `boundary` determines whether points at a distance of exactly
+    /// epsilon are included.
+    pub fn neighbourhood_by_index(
+        &self,
+        point: &[T],
+        epsilon: T::Distance,
+        boundary: Boundary,
+    ) -> Vec<usize> {
+        self.check_dim(point);
+        let mut subtree_distance = vec![T::Distance::zero(); self.dim];
+        let mut result = vec![];
+
+        let params = DynNeighbourhoodParams {
+            point,
+            epsilon,
+            boundary,
+            brute_force_size: self.brute_force_size,
+        };
+
+        self.find_neighbourhood_by_index_recursive(
+            0,
+            &self.data,
+            &params,
+            &mut subtree_distance,
+            &mut result,
+            0,
+        );
+        result
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[inline]
+    fn dispatch_find_neighbourhood_by_index_recursive_on_subtrees(
+        &self,
+        subtree1_offset: usize,
+        subtree1: &[T],
+        subtree2_offset: usize,
+        subtree2: &[T],
+        split_point: &[T],
+        params: &DynNeighbourhoodParams<T>,
+        subtree_distance: &mut [T::Distance],
+        result: &mut Vec<usize>,
+        row: usize,
+    ) {
+        let next_row = (row + 1) % self.dim;
+        self.find_neighbourhood_by_index_recursive(
+            subtree1_offset,
+            subtree1,
+            params,
+            subtree_distance,
+            result,
+            next_row,
+        );
+
+        let row_value = subtree_distance[row];
+        subtree_distance[row] = params.point[row].offset(split_point[row]);
+        if params
+            .boundary
+            .contains(T::norm(subtree_distance), params.epsilon)
+        {
+            self.find_neighbourhood_by_index_recursive(
+                subtree2_offset,
+                subtree2,
+                params,
+                subtree_distance,
+                result,
+                next_row,
+            );
+        } else {
+            query_stats::subtree_pruned();
+        }
+        subtree_distance[row] = row_value;
+    }
+
+    fn find_neighbourhood_by_index_recursive(
+        &self,
+        subtree_offset: usize,
+        subtree: &[T],
+        params: &DynNeighbourhoodParams<T>,
+        subtree_distance: &mut [T::Distance],
+        result: &mut Vec<usize>,
+        row: usize,
+    ) {
+        let _node = query_stats::enter_node();
+        let len = subtree.len() / self.dim;
+        if len <= params.brute_force_size.max(1) {
+            query_stats::leaf_scan();
+            T::slice_leaf_distances(params.point, subtree, |position, dst| {
+                if params.boundary.contains(dst, params.epsilon) {
+                    result.push(subtree_offset + position);
+                }
+            });
+        } else {
+            let split_index = len / 2;
+            let split_point = &subtree[split_index * self.dim..(split_index + 1) * self.dim];
+
+            if params
+                .boundary
+                .contains(slice_distance(split_point, params.point), params.epsilon)
+            {
+                result.push(subtree_offset + split_index);
+            }
+
+            let subtree1 = &subtree[..split_index * self.dim];
+            let subtree2 = &subtree[(split_index + 1) * self.dim..];
+            if params.point[row] <= split_point[row] {
+                self.dispatch_find_neighbourhood_by_index_recursive_on_subtrees(
+                    subtree_offset,
+                    subtree1,
+                    subtree_offset + split_index + 1,
+                    subtree2,
+                    split_point,
+                    params,
+                    subtree_distance,
+                    result,
+                    row,
+                );
+            } else if params.point[row] > split_point[row] {
+                self.dispatch_find_neighbourhood_by_index_recursive_on_subtrees(
+                    subtree_offset + split_index + 1,
+                    subtree2,
+                    subtree_offset,
+                    subtree1,
+                    split_point,
+                    params,
+                    subtree_distance,
+                    result,
+                    row,
+                );
+            }
+        }
+    }
+}
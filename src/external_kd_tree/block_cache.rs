@@ -0,0 +1,106 @@
+use alloc::{collections::BTreeMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io,
+    sync::{Mutex, PoisonError},
+};
+
+use crate::FormatScalar;
+
+/// Least recently used cache of the blocks of a file of points.
+#[derive(Debug)]
+pub(crate) struct BlockCache<T, const N: usize> {
+    inner: Mutex<Inner<T, N>>,
+}
+
+#[derive(Debug)]
+struct Inner<T, const N: usize> {
+    file: File,
+    capacity: usize,
+    /// Blocks by offset with the tick of their last use.
+    blocks: HashMap<usize, Cached<T, N>>,
+    /// Offsets of the cached blocks by the tick of their last use.
+    last_used: BTreeMap<u64, usize>,
+    tick: u64,
+    reads: usize,
+}
+
+#[derive(Debug)]
+struct Cached<T, const N: usize> {
+    block: Arc<[[T; N]]>,
+    last_used: u64,
+}
+
+impl<T: FormatScalar, const N: usize> BlockCache<T, N> {
+    pub(crate) fn new(file: File, capacity: usize) -> Self {
+        BlockCache {
+            inner: Mutex::new(Inner {
+                file,
+                capacity: capacity.max(1),
+                blocks: HashMap::new(),
+                last_used: BTreeMap::new(),
+                tick: 0,
+                reads: 0,
+            }),
+        }
+    }
+
+    /// Returns the `len` points at `offset`, reading them from the file if they are not
+    /// cached.
+    pub(crate) fn block(&self, offset: usize, len: usize) -> io::Result<Arc<[[T; N]]>> {
+        let mut inner = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        let inner = &mut *inner;
+        inner.tick += 1;
+        if let Some(cached) = inner.blocks.get_mut(&offset) {
+            inner.last_used.remove(&cached.last_used);
+            cached.last_used = inner.tick;
+            inner.last_used.insert(inner.tick, offset);
+            return Ok(cached.block.clone());
+        }
+
+        let block: Arc<[[T; N]]> =
+            super::read_points(&inner.file, super::POINTS_START, offset, len)?.into();
+        inner.reads += 1;
+        if inner.blocks.len() == inner.capacity {
+            if let Some((_, evicted)) = inner.last_used.pop_first() {
+                inner.blocks.remove(&evicted);
+            }
+        }
+        let cached = Cached {
+            block: block.clone(),
+            last_used: inner.tick,
+        };
+        inner.blocks.insert(offset, cached);
+        inner.last_used.insert(inner.tick, offset);
+        Ok(block)
+    }
+
+    pub(crate) fn reads(&self) -> usize {
+        self.inner
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .reads
+    }
+
+    pub(crate) fn set_capacity(&self, capacity: usize) {
+        let mut inner = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        inner.capacity = capacity.max(1);
+        while inner.blocks.len() > inner.capacity {
+            let Some((_, evicted)) = inner.last_used.pop_first() else {
+                break;
+            };
+            inner.blocks.remove(&evicted);
+        }
+    }
+
+    /// Number of cached blocks.
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.inner
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .blocks
+            .len()
+    }
+}
@@ -0,0 +1,482 @@
+use alloc::{vec, vec::Vec};
+use std::{
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use super::{
+    read_points, write_points, BlockCache, ExternalKdTree, Region, SplitNode, POINTS_START,
+};
+use crate::{
+    format::{ByteOrder, Header, HEADER_SIZE, LAYOUT_ROUND_ROBIN},
+    FormatError, FormatScalar,
+};
+
+/// Builder for [ExternalKdTree], which partitions a file of points into an on-disk tree.
+///
+/// The input file holds the points as consecutive little endian coordinates, like the
+/// input of [crate::PointReader]. The output file holds a header and the same points in
+/// the layout of [crate::KdTree::new], see [super]. Subtrees of up to `memory_points`
+/// points are laid out in memory, larger ones are split by passes over the files, using a
+/// scratch file next to the output that is removed afterwards.
+///
+/// ```no_run
+/// use neighbourhood::{Boundary, ExternalKdTreeBuilder};
+///
+/// let kd_tree = ExternalKdTreeBuilder::new()
+///     .block_size(4096)
+///     .cache_blocks(256)
+///     .build::<f32, 3>("points.bin", "points.tree")?;
+/// let within = kd_tree.neighbourhood(&[0.0, 0.0, 0.0], 1.0, Boundary::Closed)?;
+/// let nearest = kd_tree.knn(&[0.0, 0.0, 0.0], 10)?;
+///
+/// // Later on, the tree is opened without rebuilding it.
+/// let kd_tree = ExternalKdTreeBuilder::new().open::<f32, 3>("points.tree")?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ExternalKdTreeBuilder {
+    block_size: usize,
+    memory_points: usize,
+    cache_blocks: usize,
+    brute_force_size: usize,
+}
+
+impl Default for ExternalKdTreeBuilder {
+    fn default() -> Self {
+        ExternalKdTreeBuilder {
+            block_size: 4096,
+            memory_points: 1 << 22,
+            cache_blocks: 1024,
+            brute_force_size: 32,
+        }
+    }
+}
+
+/// Number of points read or written at once by the passes over a file.
+const CHUNK_LEN: usize = 1 << 16;
+
+impl ExternalKdTreeBuilder {
+    /// Creates a builder for blocks of 4096 points, which lays out up to `1 << 22`
+    /// points in memory and caches 1024 blocks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximal number of points of a block, at least 1. Subtrees of up to
+    /// `block_size` points are loaded as a whole, the split points of all larger
+    /// subtrees are kept in memory.
+    pub fn block_size(mut self, block_size: usize) -> Self {
+        self.block_size = block_size.max(1);
+        self
+    }
+
+    /// Sets the number of points up to which a subtree is laid out in memory during
+    /// construction.
+    pub fn memory_points(mut self, memory_points: usize) -> Self {
+        self.memory_points = memory_points.max(1);
+        self
+    }
+
+    /// Sets the number of blocks kept in the cache of the tree, at least 1.
+    pub fn cache_blocks(mut self, cache_blocks: usize) -> Self {
+        self.cache_blocks = cache_blocks.max(1);
+        self
+    }
+
+    /// Sets the `brute_force_size` of the tree.
+    pub fn brute_force_size(mut self, brute_force_size: usize) -> Self {
+        self.brute_force_size = brute_force_size;
+        self
+    }
+
+    /// Builds the tree of the points in the file at `input` into the file at `output`.
+    pub fn build<T: FormatScalar, const N: usize>(
+        &self,
+        input: impl AsRef<Path>,
+        output: impl AsRef<Path>,
+    ) -> io::Result<ExternalKdTree<T, N>> {
+        let input = File::open(input)?;
+        let len = points_len::<T, N>(&input)?;
+        let output = output.as_ref();
+        let output_file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(output)?;
+        output_file.set_len(POINTS_START + (len * N * T::SIZE) as u64)?;
+        let mut header = Vec::with_capacity(HEADER_SIZE);
+        Header {
+            layout: LAYOUT_ROUND_ROBIN,
+            len: len as u64,
+            brute_force_size: self.brute_force_size as u64,
+            leaf_size: 1,
+            stored_splits: 0,
+        }
+        .write::<T, N>(&mut header, ByteOrder::Little);
+        (&output_file).write_all(&header)?;
+
+        let mut scratch_path = OsString::from(output);
+        scratch_path.push(".scratch");
+        let mut build = Build {
+            files: [input, output_file],
+            scratch: None,
+            scratch_path: PathBuf::from(scratch_path),
+            block_size: self.block_size,
+            memory_points: self.memory_points.max(self.block_size),
+            nodes: Vec::new(),
+        };
+        let root = Region {
+            node: 0,
+            offset: 0,
+            len,
+            row: 0,
+        };
+        let result = build.build_recursive(Location::Input, root);
+        if build.scratch.take().is_some() {
+            fs::remove_file(&build.scratch_path)?;
+        }
+        result?;
+
+        let [_, output_file] = build.files;
+        Ok(self.tree(build.nodes, len, output_file))
+    }
+
+    /// Opens a tree built by [Self::build]. The split points of the subtrees larger than
+    /// `block_size` are read into memory, which need not match the block size the tree
+    /// was built with. Fails with [io::ErrorKind::InvalidData] if the header does not
+    /// belong to a tree of `T` points of dimension `N`.
+    pub fn open<T: FormatScalar, const N: usize>(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> io::Result<ExternalKdTree<T, N>> {
+        let mut file = File::open(path)?;
+        let len = tree_len::<T, N>(&mut file)?;
+        let mut nodes = Vec::new();
+        let root = Region {
+            node: 0,
+            offset: 0,
+            len,
+            row: 0,
+        };
+        read_nodes_recursive(&file, root, self.block_size, &mut nodes)?;
+        Ok(self.tree(nodes, len, file))
+    }
+
+    fn tree<T: FormatScalar, const N: usize>(
+        &self,
+        nodes: Vec<SplitNode<T, N>>,
+        len: usize,
+        file: File,
+    ) -> ExternalKdTree<T, N> {
+        ExternalKdTree {
+            nodes,
+            len,
+            block_size: self.block_size,
+            brute_force_size: self.brute_force_size,
+            blocks: BlockCache::new(file, self.cache_blocks),
+        }
+    }
+}
+
+/// Number of points stored in `file`.
+fn points_len<T: FormatScalar, const N: usize>(file: &File) -> io::Result<usize> {
+    let bytes = file.metadata()?.len() as usize;
+    let point_size = N * T::SIZE;
+    if point_size == 0 || bytes % point_size != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the file size is not a multiple of the point size",
+        ));
+    }
+    Ok(bytes / point_size)
+}
+
+/// Number of points of the tree stored in `file`, whose header has to match.
+fn tree_len<T: FormatScalar, const N: usize>(file: &mut File) -> io::Result<usize> {
+    let invalid = |error: FormatError| io::Error::new(io::ErrorKind::InvalidData, error);
+    let mut bytes = [0; HEADER_SIZE];
+    file.read_exact(&mut bytes)
+        .map_err(|_| invalid(FormatError::Magic))?;
+    let header = Header::read::<T, N>(&bytes, ByteOrder::Little).map_err(invalid)?;
+    if header.layout != LAYOUT_ROUND_ROBIN {
+        return Err(invalid(FormatError::Layout(header.layout)));
+    }
+    let len = usize::try_from(header.len).map_err(|_| invalid(FormatError::Length))?;
+    let size = len
+        .checked_mul(N * T::SIZE)
+        .and_then(|size| (size as u64).checked_add(POINTS_START));
+    if size != Some(file.metadata()?.len()) {
+        return Err(invalid(FormatError::Length));
+    }
+    Ok(len)
+}
+
+/// Reads the split points of the subtrees larger than `block_size` in pre-order.
+fn read_nodes_recursive<T: FormatScalar, const N: usize>(
+    file: &File,
+    region: Region,
+    block_size: usize,
+    nodes: &mut Vec<SplitNode<T, N>>,
+) -> io::Result<()> {
+    if region.len <= block_size {
+        return Ok(());
+    }
+    let split_index = region.len / 2;
+    let node = nodes.len();
+    nodes.push(SplitNode {
+        point: read_points(file, POINTS_START, region.offset + split_index, 1)?[0],
+        left_count: 0,
+    });
+    read_nodes_recursive(file, region.left::<N>(), block_size, nodes)?;
+    nodes[node].left_count = nodes.len() - node - 1;
+    read_nodes_recursive(
+        file,
+        region.right::<N>(nodes[node].left_count),
+        block_size,
+        nodes,
+    )
+}
+
+/// File holding the points of a subtree during construction. All files store a point at
+/// the same position, so a subtree occupies the same range in each of them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Location {
+    Input,
+    Output,
+    Scratch,
+}
+
+impl Location {
+    /// File the children of a subtree stored in `self` are partitioned into. The input
+    /// is never written and a subtree is never partitioned into its own file.
+    fn next(self) -> Location {
+        match self {
+            Location::Input | Location::Output => Location::Scratch,
+            Location::Scratch => Location::Output,
+        }
+    }
+}
+
+struct Build<T, const N: usize> {
+    /// The input and the output file.
+    files: [File; 2],
+    /// Created on first use, only trees larger than `memory_points` need it.
+    scratch: Option<File>,
+    scratch_path: PathBuf,
+    block_size: usize,
+    memory_points: usize,
+    nodes: Vec<SplitNode<T, N>>,
+}
+
+impl<T: FormatScalar, const N: usize> Build<T, N> {
+    /// Returns the file of `location` and the byte position of its first point.
+    fn file(&mut self, location: Location) -> io::Result<(&File, u64)> {
+        Ok(match location {
+            Location::Input => (&self.files[0], 0),
+            Location::Output => (&self.files[1], POINTS_START),
+            Location::Scratch => {
+                if self.scratch.is_none() {
+                    self.scratch = Some(
+                        OpenOptions::new()
+                            .read(true)
+                            .write(true)
+                            .create(true)
+                            .truncate(true)
+                            .open(&self.scratch_path)?,
+                    );
+                }
+                (self.scratch.as_ref().unwrap(), 0)
+            }
+        })
+    }
+
+    fn build_recursive(&mut self, src: Location, region: Region) -> io::Result<()> {
+        if region.len <= self.memory_points {
+            let (file, start) = self.file(src)?;
+            let mut points = read_points(file, start, region.offset, region.len)?;
+            self.layout_recursive(&mut points, region.row);
+            return write_points(&self.files[1], POINTS_START, region.offset, &points);
+        }
+
+        let split_index = region.len / 2;
+        let (median, less) = self.select_key(src, region, split_index)?;
+        let dst = src.next();
+        let split_point = self.partition(src, dst, region, median, split_index - less)?;
+        let position = region.offset + split_index;
+        write_points(&self.files[1], POINTS_START, position, &[split_point])?;
+
+        let node = self.nodes.len();
+        self.nodes.push(SplitNode {
+            point: split_point,
+            left_count: 0,
+        });
+        self.build_recursive(dst, region.left::<N>())?;
+        self.nodes[node].left_count = self.nodes.len() - node - 1;
+        self.build_recursive(dst, region.right::<N>(self.nodes[node].left_count))
+    }
+
+    /// Median split recursion like [crate::KdTree::new], which additionally records the
+    /// split points of the subtrees larger than a block.
+    fn layout_recursive(&mut self, slice: &mut [[T; N]], row: usize) {
+        if slice.len() <= 1 {
+            return;
+        }
+        let split_index = slice.len() / 2;
+        slice.select_nth_unstable_by(split_index, |lhs, rhs| {
+            lhs[row].partial_cmp(&rhs[row]).unwrap()
+        });
+
+        let node = self.nodes.len();
+        let is_node = slice.len() > self.block_size;
+        if is_node {
+            self.nodes.push(SplitNode {
+                point: slice[split_index],
+                left_count: 0,
+            });
+        }
+        let (slice1, slice2) = slice.split_at_mut(split_index);
+        let row = (row + 1) % N;
+        self.layout_recursive(slice1, row);
+        if is_node {
+            self.nodes[node].left_count = self.nodes.len() - node - 1;
+        }
+        self.layout_recursive(&mut slice2[1..], row);
+    }
+
+    /// Calls `f` with consecutive chunks of the points of `region`.
+    fn for_each_chunk(
+        &mut self,
+        location: Location,
+        region: Region,
+        mut f: impl FnMut(&[[T; N]]) -> io::Result<()>,
+    ) -> io::Result<()> {
+        let (file, start) = self.file(location)?;
+        let mut offset = region.offset;
+        let end = region.offset + region.len;
+        while offset < end {
+            let len = CHUNK_LEN.min(end - offset);
+            f(&read_points(file, start, offset, len)?)?;
+            offset += len;
+        }
+        Ok(())
+    }
+
+    /// Returns the order key of the point of `region` at `rank` along the split axis and
+    /// the number of points with a smaller key. Narrows the key down 16 bits per pass
+    /// until the candidates fit into memory.
+    fn select_key(
+        &mut self,
+        location: Location,
+        region: Region,
+        mut rank: usize,
+    ) -> io::Result<(u64, usize)> {
+        let row = region.row;
+        let mut prefix = 0u64;
+        let mut prefix_bits = 0;
+        let mut less = 0;
+        loop {
+            let matches = |key: u64| {
+                prefix_bits == 0 || key >> (64 - prefix_bits) == prefix >> (64 - prefix_bits)
+            };
+            let shift = 48 - prefix_bits;
+            let mut counts = vec![0usize; 1 << 16];
+            self.for_each_chunk(location, region, |points| {
+                for key in points.iter().map(|pt| pt[row].order_key()) {
+                    if matches(key) {
+                        counts[((key >> shift) & 0xffff) as usize] += 1;
+                    }
+                }
+                Ok(())
+            })?;
+
+            let mut bucket = 0;
+            while rank >= counts[bucket] {
+                rank -= counts[bucket];
+                less += counts[bucket];
+                bucket += 1;
+            }
+            prefix |= (bucket as u64) << shift;
+            prefix_bits += 16;
+            if prefix_bits == 64 {
+                return Ok((prefix, less));
+            }
+
+            if counts[bucket] <= self.memory_points {
+                let matches = |key: u64| key >> (64 - prefix_bits) == prefix >> (64 - prefix_bits);
+                let mut keys = Vec::with_capacity(counts[bucket]);
+                self.for_each_chunk(location, region, |points| {
+                    keys.extend(
+                        points
+                            .iter()
+                            .map(|pt| pt[row].order_key())
+                            .filter(|key| matches(*key)),
+                    );
+                    Ok(())
+                })?;
+                let (smaller, median, _) = keys.select_nth_unstable(rank);
+                let median = *median;
+                less += smaller.iter().filter(|key| **key < median).count();
+                return Ok((median, less));
+            }
+        }
+    }
+
+    /// Moves the points of `region` with a key below `median` and the first `equal_left`
+    /// points with the key `median` to the left child in `dst`, the remaining ones except
+    /// the split point to the right child. Returns the split point.
+    fn partition(
+        &mut self,
+        src: Location,
+        dst: Location,
+        region: Region,
+        median: u64,
+        mut equal_left: usize,
+    ) -> io::Result<[T; N]> {
+        let row = region.row;
+        let split_index = region.len / 2;
+        let mut left = Vec::with_capacity(CHUNK_LEN);
+        let mut right = Vec::with_capacity(CHUNK_LEN);
+        let mut split_point = None;
+
+        let mut points = Vec::new();
+        let mut offset = region.offset;
+        let end = region.offset + region.len;
+        let mut left_offset = region.offset;
+        let mut right_offset = region.offset + split_index + 1;
+        while offset < end {
+            let len = CHUNK_LEN.min(end - offset);
+            points.clear();
+            let (src_file, src_start) = self.file(src)?;
+            points.extend(read_points::<T, N>(src_file, src_start, offset, len)?);
+            offset += len;
+            for point in &points {
+                let key = point[row].order_key();
+                if key < median || (key == median && equal_left > 0) {
+                    equal_left -= usize::from(key == median);
+                    left.push(*point);
+                } else if key == median && split_point.is_none() {
+                    split_point = Some(*point);
+                } else {
+                    right.push(*point);
+                }
+            }
+
+            let (dst_file, dst_start) = self.file(dst)?;
+            if left.len() >= CHUNK_LEN || offset == end {
+                write_points(dst_file, dst_start, left_offset, &left)?;
+                left_offset += left.len();
+                left.clear();
+            }
+            if right.len() >= CHUNK_LEN || offset == end {
+                write_points(dst_file, dst_start, right_offset, &right)?;
+                right_offset += right.len();
+                right.clear();
+            }
+        }
+        Ok(split_point.expect("the median is a key of the region"))
+    }
+}
@@ -0,0 +1,158 @@
+use super::{ExternalKdTree, Region};
+use crate::{distance, query_stats, Coordinate, FormatScalar, KnnParams};
+use alloc::{vec, vec::Vec};
+use num_traits::Zero;
+use std::io;
+
+impl<T: FormatScalar, const N: usize> ExternalKdTree<T, N> {
+    /// Returns the `k` nearest points to `point` ordered by distance. Only the blocks
+    /// that may contain one of them are loaded.
+    pub fn knn(&self, point: &[T; N], k: usize) -> io::Result<Vec<(T::Distance, [T; N])>> {
+        if k == 0 || self.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut subtree_distance = [T::Distance::zero(); N];
+        let mut result = Vec::with_capacity(k);
+
+        let params = KnnParams {
+            point,
+            k: core::num::NonZero::new(k).unwrap(),
+            brute_force_size: self.brute_force_size,
+        };
+        self.find_knn_recursive(self.root(), &params, &mut subtree_distance, &mut result)?;
+        Ok(result)
+    }
+
+    #[inline]
+    fn dispatch_find_knn_recursive_on_subtrees(
+        &self,
+        (region1, region2): (Region, Region),
+        split_point: &[T; N],
+        params: &KnnParams<T, N>,
+        subtree_distance: &mut [T::Distance; N],
+        result: &mut Vec<(T::Distance, [T; N])>,
+        row: usize,
+    ) -> io::Result<()> {
+        self.find_knn_recursive(region1, params, subtree_distance, result)?;
+
+        let row_value = subtree_distance[row];
+        subtree_distance[row] = params.point[row].offset(split_point[row]);
+        if is_in_range(params, result, T::norm(subtree_distance)) {
+            self.find_knn_recursive(region2, params, subtree_distance, result)?;
+        } else {
+            query_stats::subtree_pruned();
+        }
+        subtree_distance[row] = row_value;
+        Ok(())
+    }
+
+    fn find_knn_recursive(
+        &self,
+        region: Region,
+        params: &KnnParams<T, N>,
+        subtree_distance: &mut [T::Distance; N],
+        result: &mut Vec<(T::Distance, [T; N])>,
+    ) -> io::Result<()> {
+        if region.len <= self.block_size {
+            let block = self.block(region)?;
+            find_knn_in_block(&block, region.row, params, subtree_distance, result);
+            return Ok(());
+        }
+
+        let _node = query_stats::enter_node();
+        let row = region.row;
+        let (split_point, left, right) = self.split(region);
+        let regions = if params.point[row] <= split_point[row] {
+            (left, right)
+        } else {
+            (right, left)
+        };
+        self.dispatch_find_knn_recursive_on_subtrees(
+            regions,
+            split_point,
+            params,
+            subtree_distance,
+            result,
+            row,
+        )?;
+        knn_try_insert(
+            params,
+            result,
+            distance(split_point, params.point),
+            split_point,
+        );
+        Ok(())
+    }
+}
+
+/// Recursion within a loaded block, whose first split is along `row`.
+fn find_knn_in_block<T: Coordinate, const N: usize>(
+    subtree: &[[T; N]],
+    row: usize,
+    params: &KnnParams<T, N>,
+    subtree_distance: &mut [T::Distance; N],
+    result: &mut Vec<(T::Distance, [T; N])>,
+) {
+    let _node = query_stats::enter_node();
+    if subtree.len() <= params.brute_force_size.max(1) {
+        query_stats::leaf_scan();
+        T::leaf_distances(
+            params.point,
+            subtree.len(),
+            |position| subtree[position],
+            |position, dst| knn_try_insert(params, result, dst, &subtree[position]),
+        );
+        return;
+    }
+
+    let split_index = subtree.len() / 2;
+    let split_point = &subtree[split_index];
+    let (subtree1, subtree2) = if params.point[row] <= split_point[row] {
+        (&subtree[..split_index], &subtree[(split_index + 1)..])
+    } else {
+        (&subtree[(split_index + 1)..], &subtree[..split_index])
+    };
+    let next_row = (row + 1) % N;
+    find_knn_in_block(subtree1, next_row, params, subtree_distance, result);
+
+    let row_value = subtree_distance[row];
+    subtree_distance[row] = params.point[row].offset(split_point[row]);
+    if is_in_range(params, result, T::norm(subtree_distance)) {
+        find_knn_in_block(subtree2, next_row, params, subtree_distance, result);
+    } else {
+        query_stats::subtree_pruned();
+    }
+    subtree_distance[row] = row_value;
+
+    knn_try_insert(
+        params,
+        result,
+        distance(split_point, params.point),
+        split_point,
+    );
+}
+
+/// Returns true if a point at distance `dst` may still belong to the result.
+#[inline]
+fn is_in_range<T: Coordinate, const N: usize>(
+    params: &KnnParams<T, N>,
+    result: &[(T::Distance, [T; N])],
+    dst: T::Distance,
+) -> bool {
+    result.len() < params.k.get() || dst < result.last().unwrap().0
+}
+
+#[inline]
+fn knn_try_insert<T: Coordinate, const N: usize>(
+    params: &KnnParams<T, N>,
+    result: &mut Vec<(T::Distance, [T; N])>,
+    dst: T::Distance,
+    point: &[T; N],
+) {
+    if !is_in_range(params, result, dst) {
+        return;
+    }
+    let pos = result.partition_point(|(lhs, _)| *lhs <= dst);
+    result.insert(pos, (dst, *point));
+    result.truncate(params.k.get());
+}
@@ -0,0 +1,305 @@
+//! A K-d tree for datasets larger than the memory. The points are stored in a file in
+//! the implicit layout of [crate::KdTree::new]: the split points of the top levels are
+//! kept in memory, the subtrees of up to `block_size` points are blocks of the file,
+//! which the queries load on demand through a least recently used cache.
+//!
+//! The file is in the format of [crate::format] with round robin layout, written in
+//! little endian byte order. On little endian machines it can be used by
+//! [crate::KdTreeView::from_bytes] as well.
+
+use alloc::{sync::Arc, vec, vec::Vec};
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom, Write},
+};
+
+use crate::{format::HEADER_SIZE, FormatScalar};
+
+mod block_cache;
+mod builder;
+pub mod knn;
+pub mod neighbourhood;
+
+use block_cache::BlockCache;
+pub use builder::ExternalKdTreeBuilder;
+
+/// Byte position of the first point in a tree file, which follows the header.
+const POINTS_START: u64 = HEADER_SIZE as u64;
+
+/// Split point of a subtree that is larger than a block, stored in pre-order.
+#[derive(Debug, Copy, Clone)]
+struct SplitNode<T, const N: usize> {
+    point: [T; N],
+    /// Number of nodes of the left subtree, which directly follow this one.
+    left_count: usize,
+}
+
+/// A subtree of the points at `[offset, offset + len)`, whose split node is stored at
+/// `node` unless the subtree is a block.
+#[derive(Debug, Copy, Clone)]
+struct Region {
+    node: usize,
+    offset: usize,
+    len: usize,
+    row: usize,
+}
+
+impl Region {
+    /// The subtree before the split point, whose node directly follows the one of `self`.
+    fn left<const N: usize>(self) -> Region {
+        Region {
+            node: self.node + 1,
+            offset: self.offset,
+            len: self.len / 2,
+            row: (self.row + 1) % N,
+        }
+    }
+
+    /// The subtree after the split point, whose node follows the `left_count` nodes of
+    /// the left subtree.
+    fn right<const N: usize>(self, left_count: usize) -> Region {
+        let split_index = self.len / 2;
+        Region {
+            node: self.node + 1 + left_count,
+            offset: self.offset + split_index + 1,
+            len: self.len - split_index - 1,
+            row: (self.row + 1) % N,
+        }
+    }
+}
+
+/// K-d tree whose points stay in a file, see [ExternalKdTreeBuilder].
+#[derive(Debug)]
+pub struct ExternalKdTree<T, const N: usize> {
+    nodes: Vec<SplitNode<T, N>>,
+    len: usize,
+    block_size: usize,
+
+    /// Determines the size at which the queries switch to a brute force scan within a
+    /// block.
+    pub brute_force_size: usize,
+
+    blocks: BlockCache<T, N>,
+}
+
+impl<T: FormatScalar, const N: usize> ExternalKdTree<T, N> {
+    pub const DEFAULT_BRUTE_FORCE_SIZE: usize = 32;
+
+    /// Number of points in the tree.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the tree is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Maximal number of points of a block.
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Sets the number of blocks kept in the cache, at least 1.
+    pub fn set_cache_blocks(&self, cache_blocks: usize) {
+        self.blocks.set_capacity(cache_blocks);
+    }
+
+    /// Number of blocks read from the file so far, i.e. cache misses.
+    pub fn block_reads(&self) -> usize {
+        self.blocks.reads()
+    }
+
+    fn root(&self) -> Region {
+        Region {
+            node: 0,
+            offset: 0,
+            len: self.len,
+            row: 0,
+        }
+    }
+
+    /// Returns the split point and the child subtrees of a subtree larger than a block.
+    #[inline]
+    fn split(&self, region: Region) -> (&[T; N], Region, Region) {
+        let node = &self.nodes[region.node];
+        (
+            &node.point,
+            region.left::<N>(),
+            region.right::<N>(node.left_count),
+        )
+    }
+
+    /// Loads the points of a subtree of up to `block_size` points.
+    fn block(&self, region: Region) -> io::Result<Arc<[[T; N]]>> {
+        self.blocks.block(region.offset, region.len)
+    }
+}
+
+/// Reads the `len` points at position `offset` of `file`, whose first point starts at
+/// byte `start`.
+fn read_points<T: FormatScalar, const N: usize>(
+    mut file: &File,
+    start: u64,
+    offset: usize,
+    len: usize,
+) -> io::Result<Vec<[T; N]>> {
+    let point_size = N * T::SIZE;
+    let mut bytes = vec![0; len * point_size];
+    file.seek(SeekFrom::Start(start + (offset * point_size) as u64))?;
+    file.read_exact(&mut bytes)?;
+    Ok(bytes
+        .chunks_exact(point_size)
+        .map(|bytes| core::array::from_fn(|i| T::from_le_slice(&bytes[i * T::SIZE..][..T::SIZE])))
+        .collect())
+}
+
+/// Writes `points` to position `offset` of `file`, whose first point starts at byte
+/// `start`.
+fn write_points<T: FormatScalar, const N: usize>(
+    mut file: &File,
+    start: u64,
+    offset: usize,
+    points: &[[T; N]],
+) -> io::Result<()> {
+    let point_size = N * T::SIZE;
+    let mut bytes = Vec::with_capacity(points.len() * point_size);
+    for point in points {
+        for coordinate in point {
+            coordinate.extend_le_bytes(&mut bytes);
+        }
+    }
+    file.seek(SeekFrom::Start(start + (offset * point_size) as u64))?;
+    file.write_all(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{format, vec, vec::Vec};
+    use std::{fs::File, io, path::PathBuf};
+
+    use super::{write_points, ExternalKdTreeBuilder};
+    use crate::{distance, Boundary, FormatScalar, KdTreeView};
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("neighbourhood-{}-{name}", std::process::id()))
+    }
+
+    fn check<T: FormatScalar<Distance = T> + num_traits::Float, const N: usize>(name: &str) {
+        let mut state = 7u64;
+        let mut next = move || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            // Few distinct values, so that there are many ties at the medians.
+            T::from((state >> 40) % 200).unwrap() * T::from(0.1).unwrap()
+        };
+        let data: Vec<[T; N]> = (0..5000)
+            .map(|_| core::array::from_fn(|_| next()))
+            .collect();
+        let input = temp_path(&format!("{name}.points"));
+        let output = temp_path(&format!("{name}.tree"));
+        write_points(&File::create(&input).unwrap(), 0, 0, &data).unwrap();
+
+        let builder = ExternalKdTreeBuilder::new()
+            .block_size(64)
+            .memory_points(300)
+            .cache_blocks(4);
+        let tree = builder.build::<T, N>(&input, &output).unwrap();
+        assert_eq!(tree.len(), data.len());
+        assert!(tree.block_reads() == 0 && tree.blocks.len() == 0);
+        assert!(!temp_path(&format!("{name}.tree.scratch")).exists());
+
+        // The file is a valid round robin tree in the binary format.
+        if cfg!(target_endian = "little") {
+            let bytes = std::fs::read(&output).unwrap();
+            let mut buffer = vec![0u8; bytes.len() + 9];
+            let offset = buffer.as_ptr().align_offset(8);
+            buffer[offset..offset + bytes.len()].copy_from_slice(&bytes);
+            let aligned = &buffer[offset..offset + bytes.len()];
+            let view = KdTreeView::<T, N>::from_bytes(aligned).unwrap();
+            assert_eq!(view.validate(), Ok(()));
+            let mut sorted_stored = view.data().to_vec();
+            let mut sorted_data = data.clone();
+            sorted_stored.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            sorted_data.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            assert_eq!(sorted_stored, sorted_data);
+        }
+
+        // Files of other point types or without a header are rejected.
+        let invalid_data =
+            |result: io::Result<()>| result.unwrap_err().kind() == io::ErrorKind::InvalidData;
+        assert!(invalid_data(builder.open::<T, N>(&input).map(|_| ())));
+        assert!(invalid_data(builder.open::<T, 4>(&output).map(|_| ())));
+        if T::TYPE_CODE == f32::TYPE_CODE {
+            assert!(invalid_data(builder.open::<f64, N>(&output).map(|_| ())));
+        } else {
+            assert!(invalid_data(builder.open::<f32, N>(&output).map(|_| ())));
+        }
+
+        let reopened = builder.open::<T, N>(&output).unwrap();
+        for tree in [&tree, &reopened] {
+            for _ in 0..10 {
+                let point: [T; N] = core::array::from_fn(|_| next());
+                let mut expected: Vec<T> = data.iter().map(|pt| distance(&point, pt)).collect();
+                expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+                let knn: Vec<T> = tree
+                    .knn(&point, 20)
+                    .unwrap()
+                    .iter()
+                    .map(|(d, _)| *d)
+                    .collect();
+                assert_eq!(knn, expected[..20]);
+                let epsilon = expected[30];
+                let neighbourhood = tree
+                    .neighbourhood(&point, epsilon, Boundary::Closed)
+                    .unwrap();
+                let count = expected.iter().filter(|d| **d <= epsilon).count();
+                assert_eq!(neighbourhood.len(), count);
+                assert!(neighbourhood
+                    .iter()
+                    .all(|pt| distance(&point, pt) <= epsilon));
+            }
+        }
+
+        // Small queries touch only a few of the blocks, which stay cached.
+        let reads = reopened.block_reads();
+        let _ = reopened.knn(&data[0], 1).unwrap();
+        let _ = reopened.knn(&data[0], 1).unwrap();
+        assert!(reopened.block_reads() - reads < data.len() / 64 / 4);
+        assert!(reopened.blocks.len() <= 4);
+
+        std::fs::remove_file(input).unwrap();
+        std::fs::remove_file(output).unwrap();
+    }
+
+    #[test]
+    fn external_kd_tree_test() {
+        check::<f64, 3>("f64");
+        check::<f32, 2>("f32");
+
+        // Trees that fit into a single block or the memory.
+        for len in [0, 1, 50, 250] {
+            let data: Vec<[f64; 2]> = (0..len).map(|i| [i as f64, (i % 7) as f64]).collect();
+            let input = temp_path(&format!("small{len}.points"));
+            let output = temp_path(&format!("small{len}.tree"));
+            write_points(&File::create(&input).unwrap(), 0, 0, &data).unwrap();
+            let tree = ExternalKdTreeBuilder::new()
+                .block_size(64)
+                .memory_points(100)
+                .build::<f64, 2>(&input, &output)
+                .unwrap();
+            assert_eq!(tree.len(), len);
+            assert_eq!(tree.knn(&[3.0, 3.0], 1).unwrap().len(), len.min(1));
+            assert_eq!(
+                tree.neighbourhood(&[0.0, 0.0], 1e9, Boundary::Open)
+                    .unwrap()
+                    .len(),
+                len
+            );
+            std::fs::remove_file(input).unwrap();
+            std::fs::remove_file(output).unwrap();
+        }
+    }
+}
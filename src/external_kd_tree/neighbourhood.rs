@@ -0,0 +1,155 @@
+use super::{ExternalKdTree, Region};
+use crate::{distance, query_stats, Boundary, Coordinate, FormatScalar, NeighbourhoodParams};
+use alloc::{vec, vec::Vec};
+use num_traits::Zero;
+use std::io;
+
+impl<T: FormatScalar, const N: usize> ExternalKdTree<T, N> {
+    /// Returns the points within the ball of radius epsilon around p. Only the blocks
+    /// reached by the ball are loaded.
+    /// `boundary` determines whether points at a distance of exactly epsilon are included.
+    pub fn neighbourhood(
+        &self,
+        point: &[T; N],
+        epsilon: T::Distance,
+        boundary: Boundary,
+    ) -> io::Result<Vec<[T; N]>> {
+        let mut subtree_distance = [T::Distance::zero(); N];
+        let mut result = vec![];
+        if self.is_empty() {
+            return Ok(result);
+        }
+
+        let params = NeighbourhoodParams {
+            point,
+            epsilon,
+            boundary,
+            brute_force_size: self.brute_force_size,
+        };
+        self.find_neighbourhood_recursive(
+            self.root(),
+            &params,
+            &mut subtree_distance,
+            &mut result,
+        )?;
+        Ok(result)
+    }
+
+    #[inline]
+    fn dispatch_find_neighbourhood_recursive_on_subtrees(
+        &self,
+        (region1, region2): (Region, Region),
+        split_point: &[T; N],
+        params: &NeighbourhoodParams<T, N>,
+        subtree_distance: &mut [T::Distance; N],
+        result: &mut Vec<[T; N]>,
+        row: usize,
+    ) -> io::Result<()> {
+        self.find_neighbourhood_recursive(region1, params, subtree_distance, result)?;
+
+        let row_value = subtree_distance[row];
+        subtree_distance[row] = params.point[row].offset(split_point[row]);
+        if params
+            .boundary
+            .contains(T::norm(subtree_distance), params.epsilon)
+        {
+            self.find_neighbourhood_recursive(region2, params, subtree_distance, result)?;
+        } else {
+            query_stats::subtree_pruned();
+        }
+        subtree_distance[row] = row_value;
+        Ok(())
+    }
+
+    fn find_neighbourhood_recursive(
+        &self,
+        region: Region,
+        params: &NeighbourhoodParams<T, N>,
+        subtree_distance: &mut [T::Distance; N],
+        result: &mut Vec<[T; N]>,
+    ) -> io::Result<()> {
+        if region.len <= self.block_size {
+            let block = self.block(region)?;
+            find_neighbourhood_in_block(&block, region.row, params, subtree_distance, result);
+            return Ok(());
+        }
+
+        let _node = query_stats::enter_node();
+        let row = region.row;
+        let (split_point, left, right) = self.split(region);
+        if params
+            .boundary
+            .contains(distance(split_point, params.point), params.epsilon)
+        {
+            result.push(*split_point);
+        }
+
+        let regions = if params.point[row] <= split_point[row] {
+            (left, right)
+        } else {
+            (right, left)
+        };
+        self.dispatch_find_neighbourhood_recursive_on_subtrees(
+            regions,
+            split_point,
+            params,
+            subtree_distance,
+            result,
+            row,
+        )
+    }
+}
+
+/// Recursion within a loaded block, whose first split is along `row`.
+fn find_neighbourhood_in_block<T: Coordinate, const N: usize>(
+    subtree: &[[T; N]],
+    row: usize,
+    params: &NeighbourhoodParams<T, N>,
+    subtree_distance: &mut [T::Distance; N],
+    result: &mut Vec<[T; N]>,
+) {
+    let _node = query_stats::enter_node();
+    if subtree.len() <= params.brute_force_size.max(1) {
+        query_stats::leaf_scan();
+        T::leaf_distances(
+            params.point,
+            subtree.len(),
+            |position| subtree[position],
+            |position, dst| {
+                if params.boundary.contains(dst, params.epsilon) {
+                    result.push(subtree[position]);
+                }
+            },
+        );
+        return;
+    }
+
+    let split_index = subtree.len() / 2;
+    let split_point = &subtree[split_index];
+    if params
+        .boundary
+        .contains(distance(split_point, params.point), params.epsilon)
+    {
+        result.push(*split_point);
+    }
+
+    let (subtree1, subtree2) = if params.point[row] <= split_point[row] {
+        (&subtree[..split_index], &subtree[(split_index + 1)..])
+    } else {
+        (&subtree[(split_index + 1)..], &subtree[..split_index])
+    };
+    let next_row = (row + 1) % N;
+    find_neighbourhood_in_block(subtree1, next_row, params, subtree_distance, result);
+
+    let row_value = subtree_distance[row];
+    subtree_distance[row] = params.point[row].offset(split_point[row]);
+    if params
+        .boundary
+        .contains(T::norm(subtree_distance), params.epsilon)
+    {
+        find_neighbourhood_in_block(subtree2, next_row, params, subtree_distance, result);
+    } else {
+        query_stats::subtree_pruned();
+    }
+    subtree_distance[row] = row_value;
+}
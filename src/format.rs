@@ -0,0 +1,285 @@
+//! Binary format of a [crate::KdTree] that can be used in place, e.g. from a memory map,
+//! through [crate::KdTreeView::from_bytes].
+//!
+//! Version 1 consists of a 64 byte header followed by the points in tree order and the
+//! stored split information. All values are written in the byte order of the writing
+//! machine, which is recorded in the header. The files of [crate::ExternalKdTree] use the
+//! same header with the round robin layout, but are always little endian.
+//!
+//! | Offset | Size | Content                                                        |
+//! |--------|------|----------------------------------------------------------------|
+//! | 0      | 8    | Magic bytes `NBHDKDT\0`                                        |
+//! | 8      | 4    | Version, `u32`                                                 |
+//! | 12     | 4    | Endianness tag `0x01020304`, `u32`                             |
+//! | 16     | 1    | Scalar type, 1 for `f32` and 2 for `f64`                       |
+//! | 17     | 1    | Layout, 0 for round robin, 1 for stored axes, 2 for explicit,  |
+//! |        |      | 3 for bucket leaf                                              |
+//! | 18     | 6    | Reserved, zero                                                 |
+//! | 24     | 8    | Dimension `N`, `u64`                                           |
+//! | 32     | 8    | Number of points, `u64`                                        |
+//! | 40     | 8    | `brute_force_size`, `u64`                                      |
+//! | 48     | 8    | `leaf_size`, `u64`                                             |
+//! | 56     | 8    | Layout 2: number of stored splits, `u64`. Otherwise zero       |
+//! | 64     |      | Points, `N` scalars each                                       |
+//! |        |      | Layouts 1 to 3: one split axis byte per point                  |
+//! |        |      | Layout 2: zero padding to a multiple of 8, then per stored     |
+//! |        |      | split the split position and the number of stored splits of   |
+//! |        |      | the left subtree, `u32` each, in pre-order                     |
+
+use crate::Coordinate;
+use alloc::vec::Vec;
+use core::fmt;
+
+pub(crate) const MAGIC: [u8; 8] = *b"NBHDKDT\0";
+pub(crate) const VERSION: u32 = 1;
+pub(crate) const ENDIANNESS_TAG: u32 = 0x0102_0304;
+pub(crate) const HEADER_SIZE: usize = 64;
+
+pub(crate) const LAYOUT_ROUND_ROBIN: u8 = 0;
+pub(crate) const LAYOUT_AXES: u8 = 1;
+pub(crate) const LAYOUT_EXPLICIT: u8 = 2;
+pub(crate) const LAYOUT_BUCKET_LEAF: u8 = 3;
+
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for f32 {}
+    impl Sealed for f64 {}
+}
+
+/// Scalar types that can be stored in the binary format. Every bit pattern of these
+/// types is a valid value, which allows using the stored points in place.
+pub trait FormatScalar: Coordinate + sealed::Sealed {
+    /// Identifies the scalar type in the header.
+    const TYPE_CODE: u8;
+
+    /// Size of a value in bytes.
+    const SIZE: usize;
+
+    /// Appends the bytes of `self` in native byte order.
+    fn extend_ne_bytes(self, bytes: &mut Vec<u8>);
+
+    /// Appends the bytes of `self` in little endian byte order.
+    fn extend_le_bytes(self, bytes: &mut Vec<u8>);
+
+    /// Reads a value from its `SIZE` little endian `bytes`.
+    fn from_le_slice(bytes: &[u8]) -> Self;
+
+    /// Key whose unsigned order is the total order of the values, see `total_cmp`.
+    fn order_key(self) -> u64;
+}
+
+impl FormatScalar for f32 {
+    const TYPE_CODE: u8 = 1;
+    const SIZE: usize = core::mem::size_of::<f32>();
+
+    fn extend_ne_bytes(self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_ne_bytes());
+    }
+
+    fn extend_le_bytes(self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn from_le_slice(bytes: &[u8]) -> Self {
+        f32::from_le_bytes(bytes.try_into().unwrap())
+    }
+
+    fn order_key(self) -> u64 {
+        let bits = self.to_bits();
+        let key = if bits >> (u32::BITS - 1) == 1 {
+            !bits
+        } else {
+            bits | 1 << (u32::BITS - 1)
+        };
+        (key as u64) << 32
+    }
+}
+
+impl FormatScalar for f64 {
+    const TYPE_CODE: u8 = 2;
+    const SIZE: usize = core::mem::size_of::<f64>();
+
+    fn extend_ne_bytes(self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_ne_bytes());
+    }
+
+    fn extend_le_bytes(self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_le_bytes());
+    }
+
+    fn from_le_slice(bytes: &[u8]) -> Self {
+        f64::from_le_bytes(bytes.try_into().unwrap())
+    }
+
+    fn order_key(self) -> u64 {
+        let bits = self.to_bits();
+        if bits >> (u64::BITS - 1) == 1 {
+            !bits
+        } else {
+            bits | 1 << (u64::BITS - 1)
+        }
+    }
+}
+
+/// Reason why bytes could not be loaded as a [crate::KdTreeView].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum FormatError {
+    /// The bytes do not start with the magic bytes of the format.
+    Magic,
+    /// The format version is not supported.
+    Version(u32),
+    /// The bytes were written on a machine with a different byte order.
+    Endianness,
+    /// The scalar type differs from the one of the view.
+    ScalarType(u8),
+    /// The dimension differs from the one of the view.
+    Dimension(u64),
+    /// The layout is unknown or not supported on this platform.
+    Layout(u8),
+    /// The number of bytes does not match the header.
+    Length,
+    /// The bytes are not aligned for the scalar type.
+    Alignment,
+    /// A stored split position or axis of the subtree starting at the given point
+    /// position is out of range.
+    Splits(usize),
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::Magic => write!(f, "bytes do not contain a serialized KdTree"),
+            FormatError::Version(version) => write!(f, "unsupported format version {version}"),
+            FormatError::Endianness => write!(f, "bytes were written with a different byte order"),
+            FormatError::ScalarType(code) => write!(f, "unexpected scalar type {code}"),
+            FormatError::Dimension(dimension) => write!(f, "unexpected dimension {dimension}"),
+            FormatError::Layout(layout) => write!(f, "unsupported layout {layout}"),
+            FormatError::Length => write!(f, "length does not match the header"),
+            FormatError::Alignment => write!(f, "bytes are not aligned for the scalar type"),
+            FormatError::Splits(position) => {
+                write!(f, "split at position {position} is out of range")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FormatError {}
+
+/// Byte order of the header values.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum ByteOrder {
+    /// The byte order of this machine, used by [crate::KdTree::to_bytes].
+    Native,
+    /// Little endian, used by the files of [crate::ExternalKdTree].
+    #[cfg(feature = "std")]
+    Little,
+}
+
+impl ByteOrder {
+    fn u32_bytes(self, value: u32) -> [u8; 4] {
+        match self {
+            ByteOrder::Native => value.to_ne_bytes(),
+            #[cfg(feature = "std")]
+            ByteOrder::Little => value.to_le_bytes(),
+        }
+    }
+
+    fn u64_bytes(self, value: u64) -> [u8; 8] {
+        match self {
+            ByteOrder::Native => value.to_ne_bytes(),
+            #[cfg(feature = "std")]
+            ByteOrder::Little => value.to_le_bytes(),
+        }
+    }
+
+    fn u32_at(self, bytes: &[u8], offset: usize) -> u32 {
+        let value = bytes[offset..offset + 4].try_into().unwrap();
+        match self {
+            ByteOrder::Native => u32::from_ne_bytes(value),
+            #[cfg(feature = "std")]
+            ByteOrder::Little => u32::from_le_bytes(value),
+        }
+    }
+
+    fn u64_at(self, bytes: &[u8], offset: usize) -> u64 {
+        let value = bytes[offset..offset + 8].try_into().unwrap();
+        match self {
+            ByteOrder::Native => u64::from_ne_bytes(value),
+            #[cfg(feature = "std")]
+            ByteOrder::Little => u64::from_le_bytes(value),
+        }
+    }
+}
+
+/// The values of the header that vary between trees of the same scalar type and
+/// dimension.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct Header {
+    pub(crate) layout: u8,
+    pub(crate) len: u64,
+    pub(crate) brute_force_size: u64,
+    pub(crate) leaf_size: u64,
+    /// The value at offset 56, which depends on the layout.
+    pub(crate) stored_splits: u64,
+}
+
+impl Header {
+    /// Appends the header of a tree of `T` points of dimension `N`.
+    pub(crate) fn write<T: FormatScalar, const N: usize>(
+        &self,
+        bytes: &mut Vec<u8>,
+        byte_order: ByteOrder,
+    ) {
+        bytes.extend_from_slice(&MAGIC);
+        bytes.extend_from_slice(&byte_order.u32_bytes(VERSION));
+        bytes.extend_from_slice(&byte_order.u32_bytes(ENDIANNESS_TAG));
+        bytes.extend_from_slice(&[T::TYPE_CODE, self.layout, 0, 0, 0, 0, 0, 0]);
+        for value in [
+            N as u64,
+            self.len,
+            self.brute_force_size,
+            self.leaf_size,
+            self.stored_splits,
+        ] {
+            bytes.extend_from_slice(&byte_order.u64_bytes(value));
+        }
+    }
+
+    /// Reads the header at the start of `bytes` and checks that it belongs to a tree of
+    /// `T` points of dimension `N` written in `byte_order`.
+    pub(crate) fn read<T: FormatScalar, const N: usize>(
+        bytes: &[u8],
+        byte_order: ByteOrder,
+    ) -> Result<Header, FormatError> {
+        if bytes.len() < HEADER_SIZE || bytes[..8] != MAGIC {
+            return Err(FormatError::Magic);
+        }
+        let endianness_tag = byte_order.u32_at(bytes, 12);
+        if endianness_tag != ENDIANNESS_TAG {
+            return Err(if endianness_tag.swap_bytes() == ENDIANNESS_TAG {
+                FormatError::Endianness
+            } else {
+                FormatError::Magic
+            });
+        }
+        let version = byte_order.u32_at(bytes, 8);
+        if version != VERSION {
+            return Err(FormatError::Version(version));
+        }
+        if bytes[16] != T::TYPE_CODE {
+            return Err(FormatError::ScalarType(bytes[16]));
+        }
+        let dimension = byte_order.u64_at(bytes, 24);
+        if dimension != N as u64 {
+            return Err(FormatError::Dimension(dimension));
+        }
+        Ok(Header {
+            layout: bytes[17],
+            len: byte_order.u64_at(bytes, 32),
+            brute_force_size: byte_order.u64_at(bytes, 40),
+            leaf_size: byte_order.u64_at(bytes, 48),
+            stored_splits: byte_order.u64_at(bytes, 56),
+        })
+    }
+}
@@ -0,0 +1,89 @@
+use core::fmt;
+
+/// Integer type a [crate::KdIndexTree] stores its indices in. Smaller types reduce the
+/// memory of the tree, e.g. `u32` halves it compared to `usize` on 64 bit platforms.
+pub trait TreeIndex: Copy + fmt::Debug {
+    /// Largest index that can be stored.
+    const MAX: usize;
+
+    /// Converts an index of at most [Self::MAX].
+    fn from_usize(index: usize) -> Self;
+
+    fn to_usize(self) -> usize;
+}
+
+impl TreeIndex for usize {
+    const MAX: usize = usize::MAX;
+
+    #[inline]
+    fn from_usize(index: usize) -> Self {
+        index
+    }
+
+    #[inline]
+    fn to_usize(self) -> usize {
+        self
+    }
+}
+
+impl TreeIndex for u32 {
+    const MAX: usize = if u32::BITS < usize::BITS {
+        u32::MAX as usize
+    } else {
+        usize::MAX
+    };
+
+    #[inline]
+    fn from_usize(index: usize) -> Self {
+        index as u32
+    }
+
+    #[inline]
+    fn to_usize(self) -> usize {
+        self as usize
+    }
+}
+
+impl TreeIndex for u64 {
+    const MAX: usize = if u64::BITS < usize::BITS {
+        u64::MAX as usize
+    } else {
+        usize::MAX
+    };
+
+    #[inline]
+    fn from_usize(index: usize) -> Self {
+        index as u64
+    }
+
+    #[inline]
+    fn to_usize(self) -> usize {
+        self as usize
+    }
+}
+
+/// The data of a [crate::KdIndexTree] has more points than its index type can address.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct IndexRangeError {
+    /// Number of points of the data.
+    pub len: usize,
+}
+
+impl IndexRangeError {
+    /// Checks that all indices of `len` points fit into `I`.
+    pub(crate) fn check<I: TreeIndex>(len: usize) -> Result<(), Self> {
+        match len.checked_sub(1) {
+            Some(last) if last > I::MAX => Err(IndexRangeError { len }),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for IndexRangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} points exceed the range of the index type", self.len)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for IndexRangeError {}
@@ -1,57 +1,79 @@
 use super::KdIndexTree;
-use crate::{distance, norm, NeighbourhoodParams};
-use num_traits::Float;
+use crate::{
+    bounds::NodeBoxes, distance, min_box_distance, query_stats, top_levels::TopSplits, Boundary,
+    Coordinate, NeighbourhoodParams, PointSource, TreeIndex,
+};
+use num_traits::Zero;
 
-impl<'a, T: Float + Clone, const N: usize> KdIndexTree<'a, T, N> {
-    pub fn count_neighbourhood(&self, point: &[T; N], epsilon: T) -> usize {
-        let mut subtree_distance = [T::zero(); N];
+impl<'a, T: Coordinate, const N: usize, I: TreeIndex, P: PointSource<T, N>>
+    KdIndexTree<'a, T, N, I, P>
+{
+    pub fn count_neighbourhood(
+        &self,
+        point: &[T; N],
+        epsilon: T::Distance,
+        boundary: Boundary,
+    ) -> usize {
+        let mut subtree_distance = [T::Distance::zero(); N];
 
         let params = NeighbourhoodParams {
             epsilon,
+            boundary,
             point,
-            brute_force_size: self.brute_force_size,
+            brute_force_size: self.leaf_scan_size(),
         };
 
         Self::count_neighbourhood_recursive(
-            self.data,
+            &self.data,
             &self.indices,
             &params,
             &mut subtree_distance,
-            0,
+            self.top_splits(),
+            self.node_boxes(),
         )
     }
 
+    #[allow(clippy::too_many_arguments)]
     #[inline]
     fn dispatch_count_neighbourhood_recursive_on_subtrees(
-        full_data: &[[T; N]],
-        subtree1: &[usize],
-        subtree2: &[usize],
+        full_data: &P,
+        subtree1: &[I],
+        subtree2: &[I],
         split_point: &[T; N],
         params: &NeighbourhoodParams<T, N>,
-        subtree_distance: &mut [T; N],
+        subtree_distance: &mut [T::Distance; N],
+        splits1: TopSplits<T, N>,
+        splits2: TopSplits<T, N>,
+        boxes1: NodeBoxes<T, N>,
+        boxes2: NodeBoxes<T, N>,
         row: usize,
     ) -> usize {
         let mut result = 0;
-        let next_row = (row + 1) % N;
-
         result += Self::count_neighbourhood_recursive(
             full_data,
             subtree1,
             params,
             subtree_distance,
-            next_row,
+            splits1,
+            boxes1,
         );
 
         let row_value = subtree_distance[row];
-        subtree_distance[row] = params.point[row] - split_point[row];
-        if norm(subtree_distance) <= params.epsilon {
+        subtree_distance[row] = params.point[row].offset(split_point[row]);
+        if params
+            .boundary
+            .contains(T::norm(subtree_distance), params.epsilon)
+        {
             result += Self::count_neighbourhood_recursive(
                 full_data,
                 subtree2,
                 params,
                 subtree_distance,
-                next_row,
+                splits2,
+                boxes2,
             );
+        } else {
+            query_stats::subtree_pruned();
         }
         subtree_distance[row] = row_value;
 
@@ -59,26 +81,46 @@ impl<'a, T: Float + Clone, const N: usize> KdIndexTree<'a, T, N> {
     }
 
     fn count_neighbourhood_recursive(
-        full_data: &[[T; N]],
-        subtree: &[usize],
+        full_data: &P,
+        subtree: &[I],
         params: &NeighbourhoodParams<T, N>,
-        subtree_distance: &mut [T; N],
-        row: usize,
+        subtree_distance: &mut [T::Distance; N],
+        splits: TopSplits<T, N>,
+        boxes: NodeBoxes<T, N>,
     ) -> usize {
+        let _node = query_stats::enter_node();
+        if let Some(bounds) = boxes.bounds(subtree.len()) {
+            if !params
+                .boundary
+                .contains(min_box_distance(params.point, bounds), params.epsilon)
+            {
+                query_stats::subtree_pruned();
+                return 0;
+            }
+        }
         let mut count = 0;
         if subtree.len() <= params.brute_force_size.max(1) {
-            for index in subtree {
-                let node_point = &full_data[*index];
-                if distance(node_point, params.point) <= params.epsilon {
-                    count += 1;
-                }
-            }
+            query_stats::leaf_scan();
+            T::leaf_distances(
+                params.point,
+                subtree.len(),
+                |position| full_data.point(subtree[position].to_usize()),
+                |_, dst| {
+                    if params.boundary.contains(dst, params.epsilon) {
+                        count += 1;
+                    }
+                },
+            );
         } else {
-            let split_index = subtree.len() / 2;
-            let split_node_index = subtree[split_index];
-            let split_node = &full_data[split_node_index];
+            let (split_index, row, splits1, splits2) = splits.split(subtree.len());
+            let (boxes1, boxes2) = boxes.split(subtree.len());
+            let (_, split_node) = splits.split_node(subtree, split_index, full_data);
+            let split_node = &split_node;
 
-            if distance(split_node, params.point) <= params.epsilon {
+            if params
+                .boundary
+                .contains(distance(split_node, params.point), params.epsilon)
+            {
                 count += 1;
             }
 
@@ -92,6 +134,10 @@ impl<'a, T: Float + Clone, const N: usize> KdIndexTree<'a, T, N> {
                     split_node,
                     params,
                     subtree_distance,
+                    splits1,
+                    splits2,
+                    boxes1,
+                    boxes2,
                     row,
                 );
             } else if params.point[row] > split_node[row] {
@@ -102,6 +148,10 @@ impl<'a, T: Float + Clone, const N: usize> KdIndexTree<'a, T, N> {
                     split_node,
                     params,
                     subtree_distance,
+                    splits2,
+                    splits1,
+                    boxes2,
+                    boxes1,
                     row,
                 );
             }
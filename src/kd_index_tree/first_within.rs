@@ -0,0 +1,154 @@
+use super::KdIndexTree;
+use crate::{
+    distance, query_stats, top_levels::TopSplits, Boundary, Coordinate, NeighbourhoodParams,
+    PointSource, TreeIndex,
+};
+use num_traits::Zero;
+
+impl<'a, T: Coordinate, const N: usize, I: TreeIndex, P: PointSource<T, N>>
+    KdIndexTree<'a, T, N, I, P>
+{
+    /// Returns true if at least one point lies within the ball of radius epsilon
+    /// around p. Stops at the first point found.
+    pub fn any_within(&self, point: &[T; N], epsilon: T::Distance, boundary: Boundary) -> bool {
+        self.first_within(point, epsilon, boundary).is_some()
+    }
+
+    /// Returns the index of some point within the ball of radius epsilon around p, or
+    /// `None` if there is no such point. Stops at the first point found, which is not
+    /// necessarily the closest one.
+    pub fn first_within(
+        &self,
+        point: &[T; N],
+        epsilon: T::Distance,
+        boundary: Boundary,
+    ) -> Option<usize> {
+        let mut subtree_distance = [T::Distance::zero(); N];
+
+        let params = NeighbourhoodParams {
+            epsilon,
+            boundary,
+            point,
+            brute_force_size: self.leaf_scan_size(),
+        };
+
+        Self::find_first_within_recursive(
+            &self.data,
+            &self.indices,
+            &params,
+            &mut subtree_distance,
+            self.top_splits(),
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[inline]
+    fn dispatch_find_first_within_recursive_on_subtrees(
+        full_data: &P,
+        subtree1: &[I],
+        subtree2: &[I],
+        split_point: &[T; N],
+        params: &NeighbourhoodParams<T, N>,
+        subtree_distance: &mut [T::Distance; N],
+        splits1: TopSplits<T, N>,
+        splits2: TopSplits<T, N>,
+        row: usize,
+    ) -> Option<usize> {
+        let found = Self::find_first_within_recursive(
+            full_data,
+            subtree1,
+            params,
+            subtree_distance,
+            splits1,
+        );
+        if found.is_some() {
+            return found;
+        }
+
+        let mut found = None;
+        let row_value = subtree_distance[row];
+        subtree_distance[row] = params.point[row].offset(split_point[row]);
+        if params
+            .boundary
+            .contains(T::norm(subtree_distance), params.epsilon)
+        {
+            found = Self::find_first_within_recursive(
+                full_data,
+                subtree2,
+                params,
+                subtree_distance,
+                splits2,
+            );
+        } else {
+            query_stats::subtree_pruned();
+        }
+        subtree_distance[row] = row_value;
+        found
+    }
+
+    fn find_first_within_recursive(
+        full_data: &P,
+        subtree: &[I],
+        params: &NeighbourhoodParams<T, N>,
+        subtree_distance: &mut [T::Distance; N],
+        splits: TopSplits<T, N>,
+    ) -> Option<usize> {
+        let _node = query_stats::enter_node();
+        if subtree.len() <= params.brute_force_size.max(1) {
+            query_stats::leaf_scan();
+            let mut first = None;
+            T::leaf_distances(
+                params.point,
+                subtree.len(),
+                |position| full_data.point(subtree[position].to_usize()),
+                |position, dst| {
+                    if first.is_none() && params.boundary.contains(dst, params.epsilon) {
+                        first = Some(subtree[position].to_usize());
+                    }
+                },
+            );
+            first
+        } else {
+            let (split_index, row, splits1, splits2) = splits.split(subtree.len());
+            let (split_node_index, split_node) = splits.split_node(subtree, split_index, full_data);
+            let split_node = &split_node;
+
+            if params
+                .boundary
+                .contains(distance(split_node, params.point), params.epsilon)
+            {
+                return Some(split_node_index);
+            }
+
+            let subtree1 = &subtree[..split_index];
+            let subtree2 = &subtree[(split_index + 1)..];
+            if params.point[row] <= split_node[row] {
+                Self::dispatch_find_first_within_recursive_on_subtrees(
+                    full_data,
+                    subtree1,
+                    subtree2,
+                    split_node,
+                    params,
+                    subtree_distance,
+                    splits1,
+                    splits2,
+                    row,
+                )
+            } else if params.point[row] > split_node[row] {
+                Self::dispatch_find_first_within_recursive_on_subtrees(
+                    full_data,
+                    subtree2,
+                    subtree1,
+                    split_node,
+                    params,
+                    subtree_distance,
+                    splits2,
+                    splits1,
+                    row,
+                )
+            } else {
+                None
+            }
+        }
+    }
+}
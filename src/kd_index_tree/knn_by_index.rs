@@ -1,28 +1,34 @@
 use super::KdIndexTree;
-use crate::{distance, norm, KnnParams};
+use crate::{
+    bounds::NodeBoxes, distance, min_box_distance, query_stats, top_levels::TopSplits, Coordinate,
+    KnnParams, PointSource, TreeIndex,
+};
 use alloc::{vec, vec::Vec};
-use num_traits::Float;
+use num_traits::Zero;
 
-impl<'a, T: Float + Clone, const N: usize> KdIndexTree<'a, T, N> {
-    pub fn knn_by_index(&self, point: &[T; N], k: usize) -> Vec<(T, usize)> {
+impl<'a, T: Coordinate, const N: usize, I: TreeIndex, P: PointSource<T, N>>
+    KdIndexTree<'a, T, N, I, P>
+{
+    pub fn knn_by_index(&self, point: &[T; N], k: usize) -> Vec<(T::Distance, usize)> {
         if k == 0 {
             return vec![];
         }
-        let mut subtree_distance = [T::zero(); N];
+        let mut subtree_distance = [T::Distance::zero(); N];
         let mut result = Vec::with_capacity(k);
 
         let params = KnnParams {
             point,
             k: core::num::NonZero::new(k).unwrap(),
-            brute_force_size: self.brute_force_size,
+            brute_force_size: self.leaf_scan_size(),
         };
         Self::find_knn_by_index_recursive(
-            self.data,
+            &self.data,
             &self.indices,
             &params,
             &mut subtree_distance,
             &mut result,
-            0,
+            self.top_splits(),
+            self.node_boxes(),
         );
         result
     }
@@ -30,28 +36,32 @@ impl<'a, T: Float + Clone, const N: usize> KdIndexTree<'a, T, N> {
     #[allow(clippy::too_many_arguments)]
     #[inline]
     fn dispatch_find_knn_by_index_recursive_on_subtrees(
-        full_data: &[[T; N]],
-        subtree1: &[usize],
-        subtree2: &[usize],
+        full_data: &P,
+        subtree1: &[I],
+        subtree2: &[I],
         split_point: &[T; N],
         params: &KnnParams<T, N>,
-        subtree_distance: &mut [T; N],
-        result: &mut Vec<(T, usize)>,
+        subtree_distance: &mut [T::Distance; N],
+        result: &mut Vec<(T::Distance, usize)>,
+        splits1: TopSplits<T, N>,
+        splits2: TopSplits<T, N>,
+        boxes1: NodeBoxes<T, N>,
+        boxes2: NodeBoxes<T, N>,
         row: usize,
     ) {
-        let next_row = (row + 1) % N;
         Self::find_knn_by_index_recursive(
             full_data,
             subtree1,
             params,
             subtree_distance,
             result,
-            next_row,
+            splits1,
+            boxes1,
         );
 
         let row_value = subtree_distance[row];
-        subtree_distance[row] = params.point[row] - split_point[row];
-        let dst = norm(subtree_distance);
+        subtree_distance[row] = params.point[row].offset(split_point[row]);
+        let dst = T::norm(subtree_distance);
         if result.len() < params.k.get() || dst < result.last().unwrap().0 {
             Self::find_knn_by_index_recursive(
                 full_data,
@@ -59,29 +69,48 @@ impl<'a, T: Float + Clone, const N: usize> KdIndexTree<'a, T, N> {
                 params,
                 subtree_distance,
                 result,
-                next_row,
+                splits2,
+                boxes2,
             );
+        } else {
+            query_stats::subtree_pruned();
         }
         subtree_distance[row] = row_value;
     }
 
     fn find_knn_by_index_recursive(
-        full_data: &[[T; N]],
-        subtree: &[usize],
+        full_data: &P,
+        subtree: &[I],
         params: &KnnParams<T, N>,
-        subtree_distance: &mut [T; N],
-        result: &mut Vec<(T, usize)>,
-        row: usize,
+        subtree_distance: &mut [T::Distance; N],
+        result: &mut Vec<(T::Distance, usize)>,
+        splits: TopSplits<T, N>,
+        boxes: NodeBoxes<T, N>,
     ) {
-        if subtree.len() <= params.brute_force_size.max(1) {
-            for index in subtree {
-                let node_point = &full_data[*index];
-                Self::knn_try_insert(params, result, node_point, *index);
+        let _node = query_stats::enter_node();
+        if let Some(bounds) = boxes.bounds(subtree.len()) {
+            if result.len() == params.k.get()
+                && min_box_distance(params.point, bounds) >= result.last().unwrap().0
+            {
+                query_stats::subtree_pruned();
+                return;
             }
+        }
+        if subtree.len() <= params.brute_force_size.max(1) {
+            query_stats::leaf_scan();
+            T::leaf_distances(
+                params.point,
+                subtree.len(),
+                |position| full_data.point(subtree[position].to_usize()),
+                |position, dst| {
+                    Self::knn_try_insert(params, result, dst, subtree[position].to_usize())
+                },
+            );
         } else {
-            let split_index = subtree.len() / 2;
-            let split_node_index = subtree[split_index];
-            let split_node = &full_data[split_node_index];
+            let (split_index, row, splits1, splits2) = splits.split(subtree.len());
+            let (boxes1, boxes2) = boxes.split(subtree.len());
+            let (split_node_index, split_node) = splits.split_node(subtree, split_index, full_data);
+            let split_node = &split_node;
 
             let subtree1 = &subtree[..split_index];
             let subtree2 = &subtree[(split_index + 1)..];
@@ -94,6 +123,10 @@ impl<'a, T: Float + Clone, const N: usize> KdIndexTree<'a, T, N> {
                     params,
                     subtree_distance,
                     result,
+                    splits1,
+                    splits2,
+                    boxes1,
+                    boxes2,
                     row,
                 );
             } else if params.point[row] > split_node[row] {
@@ -105,22 +138,30 @@ impl<'a, T: Float + Clone, const N: usize> KdIndexTree<'a, T, N> {
                     params,
                     subtree_distance,
                     result,
+                    splits2,
+                    splits1,
+                    boxes2,
+                    boxes1,
                     row,
                 );
             }
 
-            Self::knn_try_insert(params, result, split_node, split_node_index);
+            Self::knn_try_insert(
+                params,
+                result,
+                distance(split_node, params.point),
+                split_node_index,
+            );
         }
     }
 
     #[inline]
     fn knn_try_insert(
         params: &KnnParams<T, N>,
-        result: &mut Vec<(T, usize)>,
-        point: &[T; N],
+        result: &mut Vec<(T::Distance, usize)>,
+        dst: T::Distance,
         index: usize,
     ) {
-        let dst = distance(point, params.point);
         if result.is_empty() {
             result.push((dst, index));
         } else if result.len() < params.k.get() {
@@ -1,33 +1,144 @@
 use alloc::vec::Vec;
+use core::marker::PhantomData;
 use num_traits::Float;
 
+use crate::{
+    bounds::{BoundingBoxes, NodeBoxes},
+    knn_radii::TreeId,
+    split::{SplitLayout, Splits},
+    top_levels::{TopLevels, TopSplits},
+    Coordinate, IndexRangeError, KdTreeBuilder, OwnedPoints, PointSource, SplitAxis, TreeIndex,
+};
+
 pub mod count_neighbourhood;
+pub mod count_shell;
 pub mod filtered_knn_by_index;
+pub mod first_within;
+pub mod furthest_by_index;
 pub mod knn_by_index;
+pub mod nearest_by_index;
 pub mod neighbourhood_by_index;
+pub mod reverse_knn_by_index;
+pub mod shell_by_index;
+pub mod stats;
+#[cfg(feature = "std")]
+pub mod tune_brute_force_size;
 
-pub struct KdIndexTree<'a, T, const N: usize> {
-    indices: Vec<usize>,
+/// A K-d tree over points it does not own. The points are read from `P`, see
+/// [PointSource], and their indices are stored as `I`, see [TreeIndex].
+pub struct KdIndexTree<'a, T, const N: usize, I = usize, P = &'a [[T; N]]> {
+    pub(crate) indices: Vec<I>,
 
-    /// Reference to the points indexed in the KdIndexTree.
-    pub data: &'a [[T; N]],
+    /// The points indexed in the KdIndexTree.
+    pub(crate) data: P,
 
     /// Determines the size at which the KdIndexTree will switch
     /// to a brute force approach instead of further recursing
     /// the tree.
     pub brute_force_size: usize,
+
+    /// Split positions and axes of the nodes.
+    pub(crate) layout: SplitLayout<'static>,
+
+    /// Subtrees of up to `leaf_size` points are not split during construction.
+    pub(crate) leaf_size: usize,
+
+    /// Bounding boxes of the larger subtrees, see [crate::KdTreeBuilder::bounding_boxes].
+    pub(crate) boxes: BoundingBoxes<T, N>,
+
+    /// Contiguous copy of the top levels, see [crate::KdTreeBuilder::top_levels].
+    pub(crate) top_levels: TopLevels<T, N>,
+
+    /// Identifies the order of the points for [crate::KnnRadii].
+    pub(crate) id: TreeId,
+
+    pub(crate) _marker: PhantomData<&'a T>,
+}
+
+/// A [KdIndexTree] owning its points, e.g. as `Vec`, `Box<[_]>` or `Arc<[_]>`. Unlike a
+/// [crate::KdTree] it leaves the order of the points untouched.
+pub type OwnedKdIndexTree<T, const N: usize, D = Vec<[T; N]>, I = usize> =
+    KdIndexTree<'static, T, N, I, OwnedPoints<D>>;
+
+impl<'a, T: Coordinate, const N: usize> KdIndexTree<'a, T, N> {
+    /// Create a new K-d Index Tree.
+    pub fn new(data: &'a [[T; N]]) -> Self {
+        Self::new_unchecked(data)
+    }
+
+    /// Create a new K-d Index Tree whose split axes are chosen according to `split_axis`.
+    pub fn with_split_axis(data: &'a [[T; N]], split_axis: SplitAxis) -> Self
+    where
+        T: Float,
+    {
+        KdTreeBuilder::new()
+            .split_axis(split_axis)
+            .build_index(data)
+    }
+
+    /// Create a new K-d Index Tree and sets the `brute_force_size`.
+    pub fn with_brute_force_size(data: &'a [[T; N]], brute_force_size: usize) -> Self {
+        let mut self_ = Self::new(data);
+        self_.brute_force_size = brute_force_size;
+        self_
+    }
+}
+
+impl<'a, T: Coordinate, const N: usize, I: TreeIndex> KdIndexTree<'a, T, N, I> {
+    /// Create a new K-d Index Tree with indices of type `I`. Fails if `data` has more
+    /// points than `I` can address.
+    pub fn try_new(data: &'a [[T; N]]) -> Result<Self, IndexRangeError> {
+        Self::try_from_source(data)
+    }
+}
+
+impl<'a, T: Coordinate, const N: usize, P: PointSource<T, N>> KdIndexTree<'a, T, N, usize, P> {
+    /// Create a new K-d Index Tree over the points of `source`.
+    pub fn from_source(source: P) -> Self {
+        Self::new_unchecked(source)
+    }
 }
 
-impl<'a, T: Float + Clone, const N: usize> KdIndexTree<'a, T, N> {
-    fn select_median_with_respect_to_row_recursive(
-        slice: &mut [usize],
-        full_data: &[[T; N]],
-        row: usize,
-    ) {
+impl<T: Coordinate + 'static, const N: usize, D: AsRef<[[T; N]]>> OwnedKdIndexTree<T, N, D> {
+    /// Create a new K-d Index Tree taking ownership of `data`.
+    pub fn from_owned(data: D) -> Self {
+        Self::from_source(OwnedPoints::new(data))
+    }
+}
+
+impl<T: Coordinate + 'static, const N: usize, D: AsRef<[[T; N]]>, I: TreeIndex>
+    OwnedKdIndexTree<T, N, D, I>
+{
+    /// Create a new K-d Index Tree taking ownership of `data` with indices of type `I`.
+    /// Fails if `data` has more points than `I` can address.
+    pub fn try_from_owned(data: D) -> Result<Self, IndexRangeError> {
+        Self::try_from_source(OwnedPoints::new(data))
+    }
+
+    /// Returns the data in its original order.
+    pub fn into_data(self) -> D {
+        self.data.into_data()
+    }
+}
+
+impl<'a, T: Coordinate, const N: usize, I: TreeIndex, P: PointSource<T, N>>
+    KdIndexTree<'a, T, N, I, P>
+{
+    /// Heuristic default for `brute_force_size`. Scanning a few points is cheap compared
+    /// to a recursion step with its indirect data accesses, the more so the smaller the
+    /// points are.
+    pub const DEFAULT_BRUTE_FORCE_SIZE: usize =
+        8 + match 96usize.checked_div(N * core::mem::size_of::<T>()) {
+            Some(size) => size,
+            None => 0,
+        };
+
+    fn select_median_with_respect_to_row_recursive(slice: &mut [I], full_data: &P, row: usize) {
         let split_index = slice.len() / 2;
         slice.select_nth_unstable_by(split_index, |lhs, rhs| {
-            full_data[*lhs][row]
-                .partial_cmp(&full_data[*rhs][row])
+            full_data
+                .coord(lhs.to_usize(), row)
+                .partial_cmp(&full_data.coord(rhs.to_usize(), row))
                 .unwrap()
         });
 
@@ -46,25 +157,32 @@ impl<'a, T: Float + Clone, const N: usize> KdIndexTree<'a, T, N> {
         }
     }
 
-    /// Create a new K-d Index Tree.
-    pub fn new(data: &'a [[T; N]]) -> Self {
-        let mut indices: Vec<_> = (0..data.len()).collect();
+    /// Create a new K-d Index Tree over the points of `source` with indices of type `I`.
+    /// Fails if `source` has more points than `I` can address.
+    pub fn try_from_source(source: P) -> Result<Self, IndexRangeError> {
+        IndexRangeError::check::<I>(source.len())?;
+        Ok(Self::new_unchecked(source))
+    }
+
+    fn new_unchecked(data: P) -> Self {
+        let mut indices: Vec<_> = (0..data.len()).map(I::from_usize).collect();
 
-        Self::select_median_with_respect_to_row_recursive(&mut indices[..], data, 0);
+        if !indices.is_empty() {
+            Self::select_median_with_respect_to_row_recursive(&mut indices[..], &data, 0);
+        }
         Self {
             indices,
             data,
-            brute_force_size: 0,
+            brute_force_size: Self::DEFAULT_BRUTE_FORCE_SIZE,
+            layout: SplitLayout::RoundRobin,
+            leaf_size: 1,
+            boxes: BoundingBoxes::none(),
+            top_levels: TopLevels::none(),
+            id: TreeId::new(),
+            _marker: PhantomData,
         }
     }
 
-    /// Create a new K-d Index Tree and sets the `brute_force_size`.
-    pub fn with_brute_force_size(data: &'a [[T; N]], brute_force_size: usize) -> Self {
-        let mut self_ = Self::new(data);
-        self_.brute_force_size = brute_force_size;
-        self_
-    }
-
     /// Returns true id the KdIndexTree is empty.
     pub fn is_empty(&self) -> bool {
         self.data.is_empty()
@@ -74,20 +192,63 @@ impl<'a, T: Float + Clone, const N: usize> KdIndexTree<'a, T, N> {
     pub fn len(&self) -> usize {
         self.data.len()
     }
+
+    /// Returns a read-only reference to the indexed points.
+    pub fn data(&self) -> &P {
+        &self.data
+    }
+
+    /// Number of points up to which the queries scan a subtree by brute force.
+    fn leaf_scan_size(&self) -> usize {
+        self.brute_force_size.max(self.leaf_size)
+    }
+
+    fn splits(&self) -> Splits<'_> {
+        self.layout.splits()
+    }
+
+    fn node_boxes(&self) -> NodeBoxes<'_, T, N> {
+        self.boxes.node_boxes()
+    }
+
+    fn top_splits(&self) -> TopSplits<'_, T, N> {
+        self.top_levels.splits(self.splits())
+    }
+
+    /// Stores the bounding boxes of all subtrees larger than the current leaf scan size.
+    pub(crate) fn compute_bounding_boxes(&mut self) {
+        self.boxes = BoundingBoxes::new(
+            self.len(),
+            &|position| self.data.point(self.indices[position].to_usize()),
+            self.splits(),
+            self.leaf_scan_size(),
+        );
+    }
+
+    /// Stores a contiguous copy of the split points of the top `levels` levels.
+    pub(crate) fn compute_top_levels(&mut self, levels: usize) {
+        self.top_levels = TopLevels::new(
+            self.len(),
+            &|position| self.data.point(self.indices[position].to_usize()),
+            &|position| self.indices[position].to_usize(),
+            self.splits(),
+            self.leaf_size,
+            levels,
+        );
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use alloc::vec;
+    use alloc::{vec, vec::Vec};
 
     use super::KdIndexTree;
-    use crate::distance;
-
-    #[test]
-    fn simple_neighbourhood_query_test() {
-        let mut data = vec![];
+    use crate::{distance, scattered_points, Boundary, SplitAxis};
 
+    /// Points of a 5x5x5 grid with spacing 1 around the origin.
+    fn grid_points() -> Vec<[f64; 3]> {
         let line = [-2.0, -1.0, 0.0, 1.0, 2.0];
+        let mut data = vec![];
         for x in line {
             for y in line {
                 for z in line {
@@ -95,15 +256,541 @@ mod tests {
                 }
             }
         }
+        data
+    }
+
+    #[test]
+    fn simple_neighbourhood_query_test() {
+        let data = grid_points();
 
         let kd_index_tree = KdIndexTree::new(&data);
 
         let eps = 1.2;
         let point = [0.0, 0.0, 0.0];
-        let neighbourhood = kd_index_tree.neighbourhood_by_index(&point, eps);
+        let neighbourhood = kd_index_tree.neighbourhood_by_index(&point, eps, Boundary::Closed);
         assert_eq!(neighbourhood.len(), 7);
         for index in neighbourhood {
-            assert!(distance(&point, &data[index]) < eps);
+            assert!(distance(&point, &data[index]) <= eps);
+        }
+    }
+
+    #[test]
+    fn boundary_test() {
+        let data = grid_points();
+
+        let kd_index_tree = KdIndexTree::new(&data);
+
+        let point = [0.0, 0.0, 0.0];
+        let closed = kd_index_tree.neighbourhood_by_index(&point, 1.0, Boundary::Closed);
+        let open = kd_index_tree.neighbourhood_by_index(&point, 1.0, Boundary::Open);
+        assert_eq!(closed.len(), 7);
+        assert_eq!(open.len(), 1);
+        assert_eq!(
+            kd_index_tree.count_neighbourhood(&point, 1.0, Boundary::Closed),
+            7
+        );
+        assert_eq!(
+            kd_index_tree.count_neighbourhood(&point, 1.0, Boundary::Open),
+            1
+        );
+
+        // Shells are the difference of two balls of the same boundary kind.
+        for boundary in [Boundary::Closed, Boundary::Open] {
+            let shell = kd_index_tree.shell_by_index(&point, 1.0, 2.0, boundary);
+            let expected = kd_index_tree.count_neighbourhood(&point, 2.0, boundary)
+                - kd_index_tree.count_neighbourhood(&point, 1.0, boundary);
+            assert_eq!(shell.len(), expected);
+            assert_eq!(
+                kd_index_tree.count_shell(&point, 1.0, 2.0, boundary),
+                expected
+            );
+        }
+        assert_eq!(
+            kd_index_tree.count_shell(&point, 1.0, 2.0, Boundary::Closed),
+            12 + 8 + 6
+        );
+        assert_eq!(
+            kd_index_tree.count_shell(&point, 1.0, 2.0, Boundary::Open),
+            6 + 12 + 8
+        );
+    }
+
+    #[test]
+    fn nearest_query_test() {
+        let data = grid_points();
+
+        let kd_index_tree = KdIndexTree::new(&data);
+        assert!(KdIndexTree::<f64, 3>::new(&[])
+            .nearest_by_index(&[0.0; 3])
+            .is_none());
+
+        for point in [[0.1, 0.2, -0.1], [1.6, -0.7, 5.0], [-3.0, -3.0, -3.0]] {
+            let (dst, index) = kd_index_tree.nearest_by_index(&point).unwrap();
+            let knn = kd_index_tree.knn_by_index(&point, 1);
+            assert_eq!(dst, knn[0].0);
+            assert_eq!(data[index], data[knn[0].1]);
+        }
+    }
+
+    #[test]
+    fn shell_query_test() {
+        let data = grid_points();
+
+        let kd_index_tree = KdIndexTree::new(&data);
+
+        for (r_min, r_max) in [(1.0, 1.5), (0.5, 2.0), (1.5, 10.0), (0.0, 0.0)] {
+            for point in [[0.0, 0.0, 0.0], [1.5, -0.5, 0.3], [-4.0, 2.0, 0.0]] {
+                let expected = kd_index_tree.count_neighbourhood(&point, r_max, Boundary::Closed)
+                    - kd_index_tree.count_neighbourhood(&point, r_min, Boundary::Closed);
+                assert_eq!(
+                    kd_index_tree.count_shell(&point, r_min, r_max, Boundary::Closed),
+                    expected
+                );
+
+                let shell = kd_index_tree.shell_by_index(&point, r_min, r_max, Boundary::Closed);
+                assert_eq!(shell.len(), expected);
+                for index in shell {
+                    let dst = distance(&point, &data[index]);
+                    assert!(r_min < dst && dst <= r_max);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn first_within_query_test() {
+        let data = grid_points();
+
+        let kd_index_tree = KdIndexTree::new(&data);
+
+        for (point, eps) in [
+            ([0.5, 0.5, 0.5], 0.9),
+            ([3.0, 0.0, 0.0], 1.0),
+            ([-2.5, 2.5, 1.0], 0.5),
+        ] {
+            for boundary in [Boundary::Closed, Boundary::Open] {
+                let expected = kd_index_tree.count_neighbourhood(&point, eps, boundary) > 0;
+                assert_eq!(kd_index_tree.any_within(&point, eps, boundary), expected);
+
+                let first = kd_index_tree.first_within(&point, eps, boundary);
+                assert_eq!(first.is_some(), expected);
+                if let Some(index) = first {
+                    assert!(boundary.contains(distance(&point, &data[index]), eps));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn furthest_query_test() {
+        let data = grid_points();
+
+        let kd_index_tree = KdIndexTree::new(&data);
+
+        for point in [[0.1, 0.2, -0.1], [1.6, -0.7, 5.0], [-3.0, -3.0, -3.0]] {
+            let mut expected: Vec<_> = data.iter().map(|pt| distance(&point, pt)).collect();
+            expected.sort_by(|lhs, rhs| rhs.partial_cmp(lhs).unwrap());
+
+            let (dst, index) = kd_index_tree.furthest_by_index(&point).unwrap();
+            assert_eq!(dst, expected[0]);
+            assert_eq!(distance(&point, &data[index]), expected[0]);
+
+            for k in [1, 5, 30, 200] {
+                let k_furthest = kd_index_tree.k_furthest_by_index(&point, k);
+                let dsts: Vec<_> = k_furthest.iter().map(|(dst, _)| *dst).collect();
+                assert_eq!(dsts, expected[..k.min(expected.len())]);
+            }
+        }
+    }
+
+    #[test]
+    fn reverse_knn_query_test() {
+        let data = grid_points();
+
+        let kd_index_tree = KdIndexTree::new(&data);
+
+        for k in [1, 4, 10] {
+            let radii = kd_index_tree.knn_radii(k);
+            for (index, pt) in data.iter().enumerate() {
+                let mut dsts: Vec<_> = data
+                    .iter()
+                    .enumerate()
+                    .filter(|(other_index, _)| index != *other_index)
+                    .map(|(_, other)| distance(pt, other))
+                    .collect();
+                dsts.sort_by(|lhs, rhs| lhs.partial_cmp(rhs).unwrap());
+                assert_eq!(radii.radii()[index], dsts[k - 1]);
+            }
+
+            for point in [[0.1, 0.2, -0.1], [1.6, -0.7, 2.5], [-3.0, -3.0, -3.0]] {
+                let expected: Vec<_> = (0..data.len())
+                    .filter(|i| distance(&data[*i], &point) <= radii.radii()[*i])
+                    .collect();
+
+                let mut reverse_knn = kd_index_tree.reverse_knn_by_index_with_radii(&point, &radii);
+                reverse_knn.sort();
+                assert_eq!(reverse_knn, expected);
+                let mut reverse_knn = kd_index_tree.reverse_knn_by_index(&point, k);
+                reverse_knn.sort();
+                assert_eq!(reverse_knn, expected);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "KnnRadii were computed for a different tree.")]
+    fn reverse_knn_other_tree_test() {
+        let data: Vec<[f64; 2]> = (0..50).map(|i| [i as f64, (i % 7) as f64]).collect();
+        // Same points and length, but the KdTree radii are in tree order.
+        let radii = crate::KdTree::new(data.clone()).knn_radii(3);
+        KdIndexTree::new(&data).reverse_knn_by_index_with_radii(&[1.0, 1.0], &radii);
+    }
+
+    #[test]
+    fn split_axis_test() {
+        let mut data = vec![];
+        for x in 0..40 {
+            for y in 0..3 {
+                for z in 0..2 {
+                    data.push([x as f64 * 10.0, y as f64, z as f64 * 0.1]);
+                }
+            }
+        }
+
+        for split_axis in [
+            SplitAxis::RoundRobin,
+            SplitAxis::MaxSpread,
+            SplitAxis::MaxVariance,
+        ] {
+            let mut kd_index_tree = KdIndexTree::with_split_axis(&data, split_axis);
+            kd_index_tree.brute_force_size = 0;
+
+            for point in [[0.0, 0.0, 0.0], [123.0, 1.4, 0.05], [401.0, -1.0, 0.3]] {
+                let mut expected: Vec<_> = data.iter().map(|pt| distance(pt, &point)).collect();
+                expected.sort_by(|lhs, rhs| lhs.partial_cmp(rhs).unwrap());
+
+                let (dst, _) = kd_index_tree.nearest_by_index(&point).unwrap();
+                assert_eq!(dst, expected[0]);
+
+                let knn: Vec<_> = kd_index_tree
+                    .knn_by_index(&point, 7)
+                    .iter()
+                    .map(|(dst, _)| *dst)
+                    .collect();
+                assert_eq!(knn, expected[..7]);
+
+                let eps = 15.0;
+                let count = expected.iter().filter(|dst| **dst <= eps).count();
+                assert_eq!(
+                    kd_index_tree.count_neighbourhood(&point, eps, Boundary::Closed),
+                    count
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn stats_and_validate_test() {
+        use crate::ValidationError;
+
+        let data = grid_points();
+
+        let mut kd_index_tree = KdIndexTree::with_brute_force_size(&data, 4);
+        let stats = kd_index_tree.stats();
+        let leaf_points: usize = stats
+            .leaf_sizes
+            .iter()
+            .enumerate()
+            .map(|(n, c)| n * c)
+            .sum();
+        assert_eq!(stats.leaf_count, stats.leaf_sizes.iter().sum::<usize>());
+        assert!(stats.leaf_sizes.len() <= 5);
+        assert!(leaf_points > 0 && leaf_points < 125);
+        assert_eq!(stats.depth, 6);
+        assert_eq!(stats.level_volumes.len(), stats.depth);
+        assert_eq!(stats.level_volumes[0], 64.0);
+        assert_eq!(kd_index_tree.validate(), Ok(()));
+
+        // Move the largest point into the leftmost leaf.
+        let position = kd_index_tree
+            .indices
+            .iter()
+            .position(|index| data[*index] == [2.0, 2.0, 2.0])
+            .unwrap();
+        kd_index_tree.indices.swap(0, position);
+        assert!(matches!(
+            kd_index_tree.validate(),
+            Err(ValidationError::Partition { .. })
+        ));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn tune_brute_force_size_test() {
+        use crate::QueryKind;
+
+        let mut data = vec![];
+        for x in 0..30 {
+            for y in 0..30 {
+                data.push([x as f64, y as f64 * 0.5]);
+            }
+        }
+        let queries = [[3.2, 4.1], [-1.0, 20.0], [15.5, 7.3]];
+
+        let mut kd_index_tree = KdIndexTree::new(&data);
+        let brute_force_size = kd_index_tree.tune_brute_force_size(&queries, QueryKind::Knn(5));
+        assert_eq!(kd_index_tree.brute_force_size, brute_force_size);
+
+        for point in queries {
+            let expected = data
+                .iter()
+                .map(|pt| distance(pt, &point))
+                .fold(f64::INFINITY, f64::min);
+            assert_eq!(kd_index_tree.nearest_by_index(&point).unwrap().0, expected);
+        }
+    }
+
+    #[cfg(feature = "query-stats")]
+    #[test]
+    fn query_stats_test() {
+        use crate::take_query_stats;
+
+        let mut data = vec![];
+        for x in 0..50 {
+            for y in 0..50 {
+                data.push([x as f64, y as f64]);
+            }
+        }
+        let kd_index_tree = KdIndexTree::with_brute_force_size(&data, 4);
+
+        take_query_stats();
+        assert_eq!(
+            kd_index_tree.count_neighbourhood(&[10.0, 10.0], 1.5, Boundary::Closed),
+            9
+        );
+        let stats = take_query_stats();
+        assert!(stats.nodes_visited > 0 && stats.nodes_visited < 2500);
+        assert!(stats.subtrees_pruned > 0);
+        assert!(stats.distance_evaluations >= 9 && stats.distance_evaluations < 2500);
+        assert!(stats.leaf_scans > 0);
+        assert!(stats.max_depth > 1 && stats.max_depth <= 12);
+
+        assert_eq!(take_query_stats(), Default::default());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_test() {
+        use crate::{SerializedKdIndexTree, ValidationError};
+
+        let mut data = scattered_points(200);
+
+        let kd_index_tree = KdIndexTree::with_split_axis(&data, SplitAxis::MaxSpread);
+        let json = serde_json::to_string(&kd_index_tree).unwrap();
+        let serialized: SerializedKdIndexTree = serde_json::from_str(&json).unwrap();
+
+        let restored = KdIndexTree::from_serialized(&data, serialized.clone()).unwrap();
+        assert_eq!(restored.indices, kd_index_tree.indices);
+        assert_eq!(restored.validate(), Ok(()));
+        assert_eq!(
+            restored.knn_by_index(&[40.0, 20.0], 5),
+            kd_index_tree.knn_by_index(&[40.0, 20.0], 5)
+        );
+
+        data[17][1] += 1.0;
+        assert!(matches!(
+            KdIndexTree::from_serialized(&data, serialized.clone()),
+            Err(ValidationError::Data)
+        ));
+        assert!(matches!(
+            KdIndexTree::from_serialized(&data[1..], serialized),
+            Err(ValidationError::Data)
+        ));
+    }
+
+    #[cfg(feature = "half")]
+    #[test]
+    fn half_test() {
+        use half::f16;
+
+        let data: Vec<[f32; 2]> = scattered_points(300)
+            .iter()
+            .map(|pt| pt.map(|x| x as f32))
+            .collect();
+        let half_data: Vec<_> = data.iter().map(|pt| pt.map(f16::from_f32)).collect();
+        let kd_index_tree = KdIndexTree::new(&data);
+        let half_tree = crate::KdTreeBuilder::new()
+            .split_axis(SplitAxis::MaxSpread)
+            .build_index(&half_data);
+        assert_eq!(half_tree.validate(), Ok(()));
+
+        for point in [[0.0, 0.0], [50.0, 20.0]] {
+            let half_point = point.map(f16::from_f32);
+            assert_eq!(
+                half_tree.nearest_by_index(&half_point),
+                kd_index_tree.nearest_by_index(&point)
+            );
+            assert_eq!(
+                half_tree.count_neighbourhood(&half_point, 9.0, Boundary::Open),
+                kd_index_tree.count_neighbourhood(&point, 9.0, Boundary::Open)
+            );
+        }
+    }
+
+    #[test]
+    fn index_type_test() {
+        use crate::{IndexRangeError, KdTreeBuilder, SplitStrategy};
+
+        let data: Vec<_> = (0..500)
+            .map(|i| {
+                [
+                    (i * 37 % 101) as f32,
+                    (i * 53 % 97) as f32 * 0.5,
+                    (i % 11) as f32,
+                ]
+            })
+            .collect();
+        let kd_index_tree = KdIndexTree::new(&data);
+        let kd_index_tree_u32 = KdIndexTree::<_, 3, u32>::try_new(&data).unwrap();
+        let kd_index_tree_u64: KdIndexTree<_, 3, u64> = KdTreeBuilder::new()
+            .split_strategy(SplitStrategy::BucketLeaf)
+            .leaf_size(8)
+            .try_build_index(&data)
+            .unwrap();
+        assert_eq!(kd_index_tree_u32.validate(), Ok(()));
+        assert_eq!(kd_index_tree_u64.validate(), Ok(()));
+
+        for point in [[0.0, 0.0, 0.0], [50.0, 20.0, 5.0]] {
+            let expected = kd_index_tree.knn_by_index(&point, 9);
+            assert_eq!(kd_index_tree_u32.knn_by_index(&point, 9), expected);
+            assert_eq!(kd_index_tree_u64.knn_by_index(&point, 9), expected);
+
+            let mut expected = kd_index_tree.neighbourhood_by_index(&point, 12.0, Boundary::Open);
+            expected.sort();
+            let mut neighbourhood =
+                kd_index_tree_u32.neighbourhood_by_index(&point, 12.0, Boundary::Open);
+            neighbourhood.sort();
+            assert_eq!(neighbourhood, expected);
+        }
+
+        assert_eq!(IndexRangeError::check::<u32>(0), Ok(()));
+        #[cfg(target_pointer_width = "64")]
+        {
+            assert_eq!(IndexRangeError::check::<u32>(u32::MAX as usize + 1), Ok(()));
+            assert_eq!(
+                IndexRangeError::check::<u32>(u32::MAX as usize + 2),
+                Err(IndexRangeError {
+                    len: u32::MAX as usize + 2
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn point_source_test() {
+        use crate::{KdTreeBuilder, PointSource, Projection, SplitStrategy};
+
+        let data: Vec<_> = (0..300)
+            .map(|i| {
+                [
+                    (i * 37 % 101) as f64,
+                    (i * 53 % 97) as f64 * 0.5,
+                    (i % 13) as f64,
+                ]
+            })
+            .collect();
+        let x: Vec<_> = data.iter().map(|pt| pt[0]).collect();
+        let y: Vec<_> = data.iter().map(|pt| pt[1]).collect();
+        let z: Vec<_> = data.iter().map(|pt| pt[2]).collect();
+        let items: Vec<_> = data.iter().map(|pt| (pt[2] as usize, *pt)).collect();
+        let kd_index_tree = KdIndexTree::new(&data);
+
+        let columns = (&x[..], &y[..], &z[..]);
+        assert_eq!(columns.point(7), data[7]);
+        assert_eq!([&x[..], &y[..], &z[..]].coord(7, 1), data[7][1]);
+        let from_tuple = KdIndexTree::from_source(columns);
+        let from_array = KdIndexTree::from_source([&x[..], &y[..], &z[..]]);
+        let from_projection: KdIndexTree<_, 3, u32, _> = KdTreeBuilder::new()
+            .split_strategy(SplitStrategy::SlidingMidpoint)
+            .leaf_size(5)
+            .try_build_index_from_source(Projection::new(&items, |item: &(usize, [f64; 3])| item.1))
+            .unwrap();
+        assert_eq!(from_tuple.validate(), Ok(()));
+        assert_eq!(from_array.validate(), Ok(()));
+        assert_eq!(from_projection.validate(), Ok(()));
+
+        for point in [[0.0, 0.0, 0.0], [50.0, 20.0, 5.0]] {
+            let expected = kd_index_tree.knn_by_index(&point, 8);
+            assert_eq!(from_tuple.knn_by_index(&point, 8), expected);
+            assert_eq!(from_array.knn_by_index(&point, 8), expected);
+            assert_eq!(from_projection.knn_by_index(&point, 8), expected);
+            assert_eq!(
+                from_projection.count_neighbourhood(&point, 9.0, Boundary::Closed),
+                kd_index_tree.count_neighbourhood(&point, 9.0, Boundary::Closed)
+            );
+        }
+    }
+
+    #[test]
+    fn owned_test() {
+        use super::OwnedKdIndexTree;
+        use alloc::{boxed::Box, sync::Arc};
+
+        fn assert_send_sync<S: Send + Sync + 'static>(_: &S) {}
+
+        let data = scattered_points(300);
+        let kd_index_tree = KdIndexTree::new(&data);
+
+        let from_vec = OwnedKdIndexTree::from_owned(data.clone());
+        let from_arc = OwnedKdIndexTree::from_owned(Arc::<[[f64; 2]]>::from(data.clone()));
+        let from_box: OwnedKdIndexTree<_, 2, Box<[[f64; 2]]>, u32> =
+            OwnedKdIndexTree::try_from_owned(data.clone().into_boxed_slice()).unwrap();
+        assert_send_sync(&from_vec);
+        assert_send_sync(&from_arc);
+        assert_eq!(from_box.validate(), Ok(()));
+
+        for point in [[0.0, 0.0], [50.0, 20.0]] {
+            let expected = kd_index_tree.knn_by_index(&point, 6);
+            assert_eq!(from_vec.knn_by_index(&point, 6), expected);
+            assert_eq!(from_arc.knn_by_index(&point, 6), expected);
+            assert_eq!(from_box.knn_by_index(&point, 6), expected);
+        }
+        assert_eq!(from_arc.data().data()[..], data[..]);
+        assert_eq!(from_vec.into_data(), data);
+    }
+
+    #[test]
+    fn integer_coordinate_test() {
+        let data: Vec<[i64; 3]> = (0..300)
+            .map(|i| [i * 37 % 101, i * 53 % 97, -(i % 7) << 40])
+            .collect();
+        let kd_index_tree: KdIndexTree<_, 3, u32> = KdIndexTree::try_new(&data).unwrap();
+        assert_eq!(kd_index_tree.validate(), Ok(()));
+
+        for point in [[0, 0, 0], [50, 20, -3 << 40]] {
+            let mut expected: Vec<_> = data.iter().map(|pt| distance(&point, pt)).collect();
+            expected.sort();
+
+            // Distances are exact squared distances in i128.
+            let knn: Vec<_> = kd_index_tree
+                .knn_by_index(&point, 10)
+                .into_iter()
+                .map(|(dst, _)| dst)
+                .collect();
+            assert_eq!(knn, expected[..10]);
+            for squared_radius in [0, 100, 1 << 80, 5 << 80] {
+                let count = expected.iter().filter(|dst| **dst < squared_radius).count();
+                assert_eq!(
+                    kd_index_tree.count_neighbourhood(&point, squared_radius, Boundary::Open),
+                    count
+                );
+                assert_eq!(
+                    kd_index_tree
+                        .neighbourhood_by_index(&point, squared_radius, Boundary::Open)
+                        .len(),
+                    count
+                );
+            }
         }
     }
 }
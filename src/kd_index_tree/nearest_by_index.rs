@@ -0,0 +1,136 @@
+use super::KdIndexTree;
+use crate::{
+    distance, query_stats, top_levels::TopSplits, Coordinate, NearestParams, PointSource, TreeIndex,
+};
+use num_traits::Zero;
+
+impl<'a, T: Coordinate, const N: usize, I: TreeIndex, P: PointSource<T, N>>
+    KdIndexTree<'a, T, N, I, P>
+{
+    /// Returns the index of the point closest to `point` together with its distance,
+    /// or `None` if the KdIndexTree is empty. Equivalent to [Self::knn_by_index] with
+    /// `k = 1`, but without any allocation.
+    pub fn nearest_by_index(&self, point: &[T; N]) -> Option<(T::Distance, usize)> {
+        let first = self.indices.first()?.to_usize();
+        let mut subtree_distance = [T::Distance::zero(); N];
+
+        let params = NearestParams {
+            point,
+            brute_force_size: self.leaf_scan_size(),
+        };
+        let best = (distance(&self.data.point(first), point), first);
+        Some(Self::find_nearest_by_index_recursive(
+            &self.data,
+            &self.indices,
+            &params,
+            &mut subtree_distance,
+            best,
+            self.top_splits(),
+        ))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[inline]
+    fn dispatch_find_nearest_by_index_recursive_on_subtrees(
+        full_data: &P,
+        subtree1: &[I],
+        subtree2: &[I],
+        split_point: &[T; N],
+        params: &NearestParams<T, N>,
+        subtree_distance: &mut [T::Distance; N],
+        best: (T::Distance, usize),
+        splits1: TopSplits<T, N>,
+        splits2: TopSplits<T, N>,
+        row: usize,
+    ) -> (T::Distance, usize) {
+        let mut best = Self::find_nearest_by_index_recursive(
+            full_data,
+            subtree1,
+            params,
+            subtree_distance,
+            best,
+            splits1,
+        );
+
+        let row_value = subtree_distance[row];
+        subtree_distance[row] = params.point[row].offset(split_point[row]);
+        if T::norm(subtree_distance) < best.0 {
+            best = Self::find_nearest_by_index_recursive(
+                full_data,
+                subtree2,
+                params,
+                subtree_distance,
+                best,
+                splits2,
+            );
+        } else {
+            query_stats::subtree_pruned();
+        }
+        subtree_distance[row] = row_value;
+        best
+    }
+
+    fn find_nearest_by_index_recursive(
+        full_data: &P,
+        subtree: &[I],
+        params: &NearestParams<T, N>,
+        subtree_distance: &mut [T::Distance; N],
+        mut best: (T::Distance, usize),
+        splits: TopSplits<T, N>,
+    ) -> (T::Distance, usize) {
+        let _node = query_stats::enter_node();
+        if subtree.len() <= params.brute_force_size.max(1) {
+            query_stats::leaf_scan();
+            T::leaf_distances(
+                params.point,
+                subtree.len(),
+                |position| full_data.point(subtree[position].to_usize()),
+                |position, dst| {
+                    if dst < best.0 {
+                        best = (dst, subtree[position].to_usize());
+                    }
+                },
+            );
+        } else {
+            let (split_index, row, splits1, splits2) = splits.split(subtree.len());
+            let (split_node_index, split_node) = splits.split_node(subtree, split_index, full_data);
+            let split_node = &split_node;
+
+            let dst = distance(split_node, params.point);
+            if dst < best.0 {
+                best = (dst, split_node_index);
+            }
+
+            let subtree1 = &subtree[..split_index];
+            let subtree2 = &subtree[(split_index + 1)..];
+            if params.point[row] <= split_node[row] {
+                best = Self::dispatch_find_nearest_by_index_recursive_on_subtrees(
+                    full_data,
+                    subtree1,
+                    subtree2,
+                    split_node,
+                    params,
+                    subtree_distance,
+                    best,
+                    splits1,
+                    splits2,
+                    row,
+                );
+            } else if params.point[row] > split_node[row] {
+                best = Self::dispatch_find_nearest_by_index_recursive_on_subtrees(
+                    full_data,
+                    subtree2,
+                    subtree1,
+                    split_node,
+                    params,
+                    subtree_distance,
+                    best,
+                    splits2,
+                    splits1,
+                    row,
+                );
+            }
+        }
+        best
+    }
+}
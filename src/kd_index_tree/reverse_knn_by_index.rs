@@ -0,0 +1,184 @@
+use super::KdIndexTree;
+use crate::{
+    distance, query_stats, top_levels::TopSplits, Coordinate, KnnRadii, PointSource,
+    ReverseKnnParams, TreeIndex,
+};
+use alloc::{vec, vec::Vec};
+use num_traits::Zero;
+
+impl<'a, T: Coordinate, const N: usize, I: TreeIndex, P: PointSource<T, N>>
+    KdIndexTree<'a, T, N, I, P>
+{
+    /// Computes the distance of every point to its k-th nearest neighbour. The result
+    /// can be passed to [Self::reverse_knn_by_index_with_radii] for any number of queries
+    /// on this tree.
+    pub fn knn_radii(&self, k: usize) -> KnnRadii<T::Distance> {
+        let radii = (0..self.len())
+            .map(
+                |index| match self.knn_by_index(&self.data.point(index), k + 1).get(k) {
+                    Some((dst, _)) => *dst,
+                    None => T::MAX_DISTANCE,
+                },
+            )
+            .collect();
+        KnnRadii::new::<N>(
+            self.id,
+            k,
+            radii,
+            |position| self.indices[position].to_usize(),
+            self.splits(),
+            self.leaf_size,
+        )
+    }
+
+    /// Returns the index of all points that have `point` among their k nearest
+    /// neighbours. Computes the [KnnRadii] of all points first, use
+    /// [Self::reverse_knn_by_index_with_radii] to reuse them for several queries.
+    pub fn reverse_knn_by_index(&self, point: &[T; N], k: usize) -> Vec<usize> {
+        self.reverse_knn_by_index_with_radii(point, &self.knn_radii(k))
+    }
+
+    /// Like [Self::reverse_knn_by_index] with radii precomputed by [Self::knn_radii] of
+    /// this tree. Only subtrees containing a point whose knn ball can contain `point`
+    /// are visited. Panics if `radii` were computed by another tree.
+    pub fn reverse_knn_by_index_with_radii(
+        &self,
+        point: &[T; N],
+        radii: &KnnRadii<T::Distance>,
+    ) -> Vec<usize> {
+        radii.assert_tree(self.id);
+        let mut result = vec![];
+        let mut subtree_distance = [T::Distance::zero(); N];
+
+        let params = ReverseKnnParams {
+            point,
+            radii: radii.radii(),
+            brute_force_size: self.leaf_scan_size(),
+        };
+
+        Self::find_reverse_knn_by_index_recursive(
+            &self.data,
+            &self.indices,
+            radii.subtree_max(),
+            &params,
+            &mut subtree_distance,
+            &mut result,
+            self.top_splits(),
+        );
+        result
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[inline]
+    fn dispatch_find_reverse_knn_by_index_recursive_on_subtrees(
+        full_data: &P,
+        subtree1: &[I],
+        subtree1_max: &[T::Distance],
+        subtree2: &[I],
+        subtree2_max: &[T::Distance],
+        split_point: &[T; N],
+        params: &ReverseKnnParams<T, N>,
+        subtree_distance: &mut [T::Distance; N],
+        result: &mut Vec<usize>,
+        splits1: TopSplits<T, N>,
+        splits2: TopSplits<T, N>,
+        row: usize,
+    ) {
+        Self::find_reverse_knn_by_index_recursive(
+            full_data,
+            subtree1,
+            subtree1_max,
+            params,
+            subtree_distance,
+            result,
+            splits1,
+        );
+
+        let row_value = subtree_distance[row];
+        subtree_distance[row] = params.point[row].offset(split_point[row]);
+        Self::find_reverse_knn_by_index_recursive(
+            full_data,
+            subtree2,
+            subtree2_max,
+            params,
+            subtree_distance,
+            result,
+            splits2,
+        );
+        subtree_distance[row] = row_value;
+    }
+
+    fn find_reverse_knn_by_index_recursive(
+        full_data: &P,
+        subtree: &[I],
+        subtree_max: &[T::Distance],
+        params: &ReverseKnnParams<T, N>,
+        subtree_distance: &mut [T::Distance; N],
+        result: &mut Vec<usize>,
+        splits: TopSplits<T, N>,
+    ) {
+        let _node = query_stats::enter_node();
+        if subtree.len() <= params.brute_force_size.max(1) {
+            query_stats::leaf_scan();
+            T::leaf_distances(
+                params.point,
+                subtree.len(),
+                |position| full_data.point(subtree[position].to_usize()),
+                |position, dst| {
+                    let index = subtree[position].to_usize();
+                    if dst <= params.radii[index] {
+                        result.push(index);
+                    }
+                },
+            );
+        } else {
+            let (split_index, row, splits1, splits2) = splits.split(subtree.len());
+            if T::norm(subtree_distance) > subtree_max[split_index] {
+                query_stats::subtree_pruned();
+                return;
+            }
+            let (split_node_index, split_node) = splits.split_node(subtree, split_index, full_data);
+            let split_node = &split_node;
+
+            if distance(split_node, params.point) <= params.radii[split_node_index] {
+                result.push(split_node_index);
+            }
+
+            let subtree1 = &subtree[..split_index];
+            let subtree2 = &subtree[(split_index + 1)..];
+            let subtree1_max = &subtree_max[..split_index];
+            let subtree2_max = &subtree_max[(split_index + 1)..];
+            if params.point[row] <= split_node[row] {
+                Self::dispatch_find_reverse_knn_by_index_recursive_on_subtrees(
+                    full_data,
+                    subtree1,
+                    subtree1_max,
+                    subtree2,
+                    subtree2_max,
+                    split_node,
+                    params,
+                    subtree_distance,
+                    result,
+                    splits1,
+                    splits2,
+                    row,
+                );
+            } else if params.point[row] > split_node[row] {
+                Self::dispatch_find_reverse_knn_by_index_recursive_on_subtrees(
+                    full_data,
+                    subtree2,
+                    subtree2_max,
+                    subtree1,
+                    subtree1_max,
+                    split_node,
+                    params,
+                    subtree_distance,
+                    result,
+                    splits2,
+                    splits1,
+                    row,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use super::KdIndexTree;
+use crate::{
+    distance, max_box_distance, query_stats, top_levels::TopSplits, unbounded_box, Boundary,
+    Coordinate, PointSource, ShellParams, TreeIndex,
+};
+use alloc::{vec, vec::Vec};
+use num_traits::Zero;
+
+impl<'a, T: Coordinate, const N: usize, I: TreeIndex, P: PointSource<T, N>>
+    KdIndexTree<'a, T, N, I, P>
+{
+    /// Returns the index of all points inside the ball of radius `r_max` but outside
+    /// the ball of radius `r_min` around p. With [Boundary::Closed] these are the points
+    /// with `r_min < distance <= r_max`, with [Boundary::Open] the points with
+    /// `r_min <= distance < r_max`. The list of indices can be used together with
+    /// [Self::data] to retrieve the points.
+    pub fn shell_by_index(
+        &self,
+        point: &[T; N],
+        r_min: T::Distance,
+        r_max: T::Distance,
+        boundary: Boundary,
+    ) -> Vec<usize> {
+        let mut result = vec![];
+        let mut subtree_distance = [T::Distance::zero(); N];
+        let mut bounds = unbounded_box();
+
+        let params = ShellParams {
+            point,
+            r_min,
+            r_max,
+            boundary,
+            brute_force_size: self.leaf_scan_size(),
+        };
+
+        Self::find_shell_by_index_recursive(
+            &self.data,
+            &self.indices,
+            &params,
+            &mut subtree_distance,
+            &mut bounds,
+            &mut result,
+            self.top_splits(),
+        );
+        result
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[inline]
+    fn dispatch_find_shell_by_index_recursive_on_subtrees(
+        full_data: &P,
+        subtree1: &[I],
+        subtree2: &[I],
+        split_point: &[T; N],
+        params: &ShellParams<T, N>,
+        subtree_distance: &mut [T::Distance; N],
+        bounds: &mut [[T; N]; 2],
+        result: &mut Vec<usize>,
+        splits1: TopSplits<T, N>,
+        splits2: TopSplits<T, N>,
+        row: usize,
+    ) {
+        let (near_bound, far_bound) = if params.point[row] <= split_point[row] {
+            (1, 0)
+        } else {
+            (0, 1)
+        };
+
+        let bound_value = bounds[near_bound][row];
+        bounds[near_bound][row] = split_point[row];
+        Self::find_shell_by_index_recursive(
+            full_data,
+            subtree1,
+            params,
+            subtree_distance,
+            bounds,
+            result,
+            splits1,
+        );
+        bounds[near_bound][row] = bound_value;
+
+        let row_value = subtree_distance[row];
+        subtree_distance[row] = params.point[row].offset(split_point[row]);
+        if params
+            .boundary
+            .contains(T::norm(subtree_distance), params.r_max)
+        {
+            let bound_value = bounds[far_bound][row];
+            bounds[far_bound][row] = split_point[row];
+            Self::find_shell_by_index_recursive(
+                full_data,
+                subtree2,
+                params,
+                subtree_distance,
+                bounds,
+                result,
+                splits2,
+            );
+            bounds[far_bound][row] = bound_value;
+        } else {
+            query_stats::subtree_pruned();
+        }
+        subtree_distance[row] = row_value;
+    }
+
+    fn find_shell_by_index_recursive(
+        full_data: &P,
+        subtree: &[I],
+        params: &ShellParams<T, N>,
+        subtree_distance: &mut [T::Distance; N],
+        bounds: &mut [[T; N]; 2],
+        result: &mut Vec<usize>,
+        splits: TopSplits<T, N>,
+    ) {
+        let _node = query_stats::enter_node();
+        if params
+            .boundary
+            .contains(max_box_distance(params.point, bounds), params.r_min)
+        {
+            query_stats::subtree_pruned();
+            return;
+        }
+
+        if subtree.len() <= params.brute_force_size.max(1) {
+            query_stats::leaf_scan();
+            T::leaf_distances(
+                params.point,
+                subtree.len(),
+                |position| full_data.point(subtree[position].to_usize()),
+                |position, dst| {
+                    if params.boundary.contains(dst, params.r_max)
+                        && !params.boundary.contains(dst, params.r_min)
+                    {
+                        result.push(subtree[position].to_usize());
+                    }
+                },
+            );
+        } else {
+            let (split_index, row, splits1, splits2) = splits.split(subtree.len());
+            let (split_node_index, split_node) = splits.split_node(subtree, split_index, full_data);
+            let split_node = &split_node;
+
+            let dst = distance(split_node, params.point);
+            if params.boundary.contains(dst, params.r_max)
+                && !params.boundary.contains(dst, params.r_min)
+            {
+                result.push(split_node_index);
+            }
+
+            let subtree1 = &subtree[..split_index];
+            let subtree2 = &subtree[(split_index + 1)..];
+            if params.point[row] <= split_node[row] {
+                Self::dispatch_find_shell_by_index_recursive_on_subtrees(
+                    full_data,
+                    subtree1,
+                    subtree2,
+                    split_node,
+                    params,
+                    subtree_distance,
+                    bounds,
+                    result,
+                    splits1,
+                    splits2,
+                    row,
+                );
+            } else if params.point[row] > split_node[row] {
+                Self::dispatch_find_shell_by_index_recursive_on_subtrees(
+                    full_data,
+                    subtree2,
+                    subtree1,
+                    split_node,
+                    params,
+                    subtree_distance,
+                    bounds,
+                    result,
+                    splits2,
+                    splits1,
+                    row,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use super::KdIndexTree;
+use crate::{
+    stats::{collect_tree_stats, validate_partition},
+    Coordinate, PointSource, TreeIndex, TreeStats, ValidationError,
+};
+use alloc::vec;
+use num_traits::Float;
+
+impl<'a, T: Coordinate, const N: usize, I: TreeIndex, P: PointSource<T, N>>
+    KdIndexTree<'a, T, N, I, P>
+{
+    /// Returns the depth, the leaf sizes and the bounding box volumes per level of the
+    /// KdIndexTree. Leaves are the subtrees queries scan by brute force with the
+    /// current `brute_force_size`.
+    pub fn stats(&self) -> TreeStats<T>
+    where
+        T: Float,
+    {
+        collect_tree_stats(
+            self.indices.len(),
+            &|position| self.data.point(self.indices[position].to_usize()),
+            self.splits(),
+            self.leaf_scan_size(),
+        )
+    }
+
+    /// Checks that the indices are a permutation of the data indices and that the
+    /// split point of every node partitions its subtree: all points before it are less
+    /// than or equal and all points after it are greater than or equal on the split
+    /// axis.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.validate_indices()?;
+        if !self.layout.has_len(self.len()) {
+            return Err(ValidationError::Layout { position: 0 });
+        }
+
+        validate_partition(
+            self.len(),
+            &|position| self.data.point(self.indices[position].to_usize()),
+            self.splits(),
+            self.leaf_size,
+        )
+    }
+
+    pub(crate) fn validate_indices(&self) -> Result<(), ValidationError> {
+        if self.indices.len() != self.data.len() {
+            return Err(ValidationError::Indices);
+        }
+        let mut seen = vec![false; self.data.len()];
+        for index in &self.indices {
+            match seen.get_mut(index.to_usize()) {
+                Some(seen) if !*seen => *seen = true,
+                _ => return Err(ValidationError::Indices),
+            }
+        }
+        Ok(())
+    }
+}
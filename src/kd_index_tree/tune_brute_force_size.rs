@@ -0,0 +1,37 @@
+use super::KdIndexTree;
+use crate::{tune::fastest_brute_force_size, Coordinate, PointSource, QueryKind, TreeIndex};
+use core::hint::black_box;
+
+impl<'a, T: Coordinate, const N: usize, I: TreeIndex, P: PointSource<T, N>>
+    KdIndexTree<'a, T, N, I, P>
+{
+    /// Times `sample_queries` of the given kind for a range of candidate values, sets
+    /// the fastest one as `brute_force_size` and returns it. The samples should be
+    /// representative for the actual workload.
+    pub fn tune_brute_force_size(
+        &mut self,
+        sample_queries: &[[T; N]],
+        query_kind: QueryKind<T::Distance>,
+    ) -> usize {
+        self.brute_force_size = fastest_brute_force_size(|brute_force_size| {
+            self.brute_force_size = brute_force_size;
+            for point in sample_queries {
+                match query_kind {
+                    QueryKind::Nearest => {
+                        black_box(self.nearest_by_index(point));
+                    }
+                    QueryKind::Knn(k) => {
+                        black_box(self.knn_by_index(point, k));
+                    }
+                    QueryKind::Neighbourhood(epsilon, boundary) => {
+                        black_box(self.neighbourhood_by_index(point, epsilon, boundary));
+                    }
+                    QueryKind::CountNeighbourhood(epsilon, boundary) => {
+                        black_box(self.count_neighbourhood(point, epsilon, boundary));
+                    }
+                }
+            }
+        });
+        self.brute_force_size
+    }
+}
@@ -1,38 +1,72 @@
-use super::KdTree;
-use crate::{distance, norm, NeighbourhoodParams};
-use num_traits::Float;
+use super::GenericKdTree;
+use crate::{
+    bounds::NodeBoxes, distance, min_box_distance, query_stats, top_levels::TopSplits, Boundary,
+    Coordinate, NeighbourhoodParams,
+};
+use num_traits::Zero;
 
-impl<T: Float + Clone, const N: usize> KdTree<T, N> {
-    pub fn count_neighbourhood(&self, point: &[T; N], epsilon: T) -> usize {
-        let mut subtree_distance = [T::zero(); N];
+impl<T: Coordinate, const N: usize, D: AsRef<[[T; N]]>> GenericKdTree<'_, T, N, D> {
+    pub fn count_neighbourhood(
+        &self,
+        point: &[T; N],
+        epsilon: T::Distance,
+        boundary: Boundary,
+    ) -> usize {
+        let mut subtree_distance = [T::Distance::zero(); N];
         let params = NeighbourhoodParams {
             point,
             epsilon,
-            brute_force_size: self.brute_force_size,
+            boundary,
+            brute_force_size: self.leaf_scan_size(),
         };
 
-        Self::count_neighbourhood_recursive(&self.data, &params, &mut subtree_distance, 0)
+        Self::count_neighbourhood_recursive(
+            self.data(),
+            &params,
+            &mut subtree_distance,
+            self.top_splits(),
+            self.node_boxes(),
+        )
     }
 
+    #[allow(clippy::too_many_arguments)]
     #[inline]
     fn dispatch_count_neighbourhood_recursive_on_subtrees<'a>(
         subtree1: &'a [[T; N]],
         subtree2: &'a [[T; N]],
         split_point: &[T; N],
         params: &NeighbourhoodParams<T, N>,
-        subtree_distance: &mut [T; N],
+        subtree_distance: &mut [T::Distance; N],
+        splits1: TopSplits<T, N>,
+        splits2: TopSplits<T, N>,
+        boxes1: NodeBoxes<T, N>,
+        boxes2: NodeBoxes<T, N>,
         row: usize,
     ) -> usize {
         let mut result = 0;
-        let next_row = (row + 1) % N;
-
-        result += Self::count_neighbourhood_recursive(subtree1, params, subtree_distance, next_row);
+        result += Self::count_neighbourhood_recursive(
+            subtree1,
+            params,
+            subtree_distance,
+            splits1,
+            boxes1,
+        );
 
         let row_value = subtree_distance[row];
-        subtree_distance[row] = params.point[row] - split_point[row];
-        if norm(subtree_distance) <= params.epsilon {
-            result +=
-                Self::count_neighbourhood_recursive(subtree2, params, subtree_distance, next_row);
+        subtree_distance[row] = params.point[row].offset(split_point[row]);
+        if params
+            .boundary
+            .contains(T::norm(subtree_distance), params.epsilon)
+        {
+            result += Self::count_neighbourhood_recursive(
+                subtree2,
+                params,
+                subtree_distance,
+                splits2,
+                boxes2,
+            );
+        } else {
+            query_stats::subtree_pruned();
         }
         subtree_distance[row] = row_value;
 
@@ -42,21 +76,42 @@ impl<T: Float + Clone, const N: usize> KdTree<T, N> {
     fn count_neighbourhood_recursive(
         subtree: &[[T; N]],
         params: &NeighbourhoodParams<T, N>,
-        subtree_distance: &mut [T; N],
-        row: usize,
+        subtree_distance: &mut [T::Distance; N],
+        splits: TopSplits<T, N>,
+        boxes: NodeBoxes<T, N>,
     ) -> usize {
+        let _node = query_stats::enter_node();
+        if let Some(bounds) = boxes.bounds(subtree.len()) {
+            if !params
+                .boundary
+                .contains(min_box_distance(params.point, bounds), params.epsilon)
+            {
+                query_stats::subtree_pruned();
+                return 0;
+            }
+        }
         let mut count = 0;
         if subtree.len() <= params.brute_force_size.max(1) {
-            for pt in subtree.iter() {
-                if distance(params.point, pt) <= params.epsilon {
-                    count += 1;
-                }
-            }
+            query_stats::leaf_scan();
+            T::leaf_distances(
+                params.point,
+                subtree.len(),
+                |position| subtree[position],
+                |_, dst| {
+                    if params.boundary.contains(dst, params.epsilon) {
+                        count += 1;
+                    }
+                },
+            );
         } else {
-            let split_index = subtree.len() / 2;
-            let split_point = &subtree[split_index];
+            let (split_index, row, splits1, splits2) = splits.split(subtree.len());
+            let (boxes1, boxes2) = boxes.split(subtree.len());
+            let split_point = splits.split_point(subtree, split_index);
 
-            if distance(split_point, params.point) <= params.epsilon {
+            if params
+                .boundary
+                .contains(distance(split_point, params.point), params.epsilon)
+            {
                 count += 1;
             }
 
@@ -69,6 +124,10 @@ impl<T: Float + Clone, const N: usize> KdTree<T, N> {
                     split_point,
                     params,
                     subtree_distance,
+                    splits1,
+                    splits2,
+                    boxes1,
+                    boxes2,
                     row,
                 );
             } else if params.point[row] > split_point[row] {
@@ -78,6 +137,10 @@ impl<T: Float + Clone, const N: usize> KdTree<T, N> {
                     split_point,
                     params,
                     subtree_distance,
+                    splits2,
+                    splits1,
+                    boxes2,
+                    boxes1,
                     row,
                 );
             }
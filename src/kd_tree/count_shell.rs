@@ -0,0 +1,158 @@
+use super::GenericKdTree;
+use crate::{
+    distance, max_box_distance, min_box_distance, query_stats, top_levels::TopSplits,
+    unbounded_box, Boundary, Coordinate, ShellParams,
+};
+use num_traits::Zero;
+
+impl<T: Coordinate, const N: usize, D: AsRef<[[T; N]]>> GenericKdTree<'_, T, N, D> {
+    /// Counts the points inside the ball of radius `r_max` but outside the ball of
+    /// radius `r_min` around p. See [Self::shell] for the meaning of `boundary`.
+    pub fn count_shell(
+        &self,
+        point: &[T; N],
+        r_min: T::Distance,
+        r_max: T::Distance,
+        boundary: Boundary,
+    ) -> usize {
+        let mut subtree_distance = [T::Distance::zero(); N];
+        let mut bounds = unbounded_box();
+        let params = ShellParams {
+            point,
+            r_min,
+            r_max,
+            boundary,
+            brute_force_size: self.leaf_scan_size(),
+        };
+
+        Self::count_shell_recursive(
+            self.data(),
+            &params,
+            &mut subtree_distance,
+            &mut bounds,
+            self.top_splits(),
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[inline]
+    fn dispatch_count_shell_recursive_on_subtrees(
+        subtree1: &[[T; N]],
+        subtree2: &[[T; N]],
+        split_point: &[T; N],
+        params: &ShellParams<T, N>,
+        subtree_distance: &mut [T::Distance; N],
+        bounds: &mut [[T; N]; 2],
+        splits1: TopSplits<T, N>,
+        splits2: TopSplits<T, N>,
+        row: usize,
+    ) -> usize {
+        let mut result = 0;
+        let (near_bound, far_bound) = if params.point[row] <= split_point[row] {
+            (1, 0)
+        } else {
+            (0, 1)
+        };
+
+        let bound_value = bounds[near_bound][row];
+        bounds[near_bound][row] = split_point[row];
+        result += Self::count_shell_recursive(subtree1, params, subtree_distance, bounds, splits1);
+        bounds[near_bound][row] = bound_value;
+
+        let row_value = subtree_distance[row];
+        subtree_distance[row] = params.point[row].offset(split_point[row]);
+        if params
+            .boundary
+            .contains(T::norm(subtree_distance), params.r_max)
+        {
+            let bound_value = bounds[far_bound][row];
+            bounds[far_bound][row] = split_point[row];
+            result +=
+                Self::count_shell_recursive(subtree2, params, subtree_distance, bounds, splits2);
+            bounds[far_bound][row] = bound_value;
+        } else {
+            query_stats::subtree_pruned();
+        }
+        subtree_distance[row] = row_value;
+
+        result
+    }
+
+    fn count_shell_recursive(
+        subtree: &[[T; N]],
+        params: &ShellParams<T, N>,
+        subtree_distance: &mut [T::Distance; N],
+        bounds: &mut [[T; N]; 2],
+        splits: TopSplits<T, N>,
+    ) -> usize {
+        let _node = query_stats::enter_node();
+        let max_distance = max_box_distance(params.point, bounds);
+        if params.boundary.contains(max_distance, params.r_min) {
+            query_stats::subtree_pruned();
+            return 0;
+        } else if params.boundary.contains(max_distance, params.r_max)
+            && !params
+                .boundary
+                .contains(min_box_distance(params.point, bounds), params.r_min)
+        {
+            query_stats::subtree_pruned();
+            return subtree.len();
+        }
+
+        let mut count = 0;
+        if subtree.len() <= params.brute_force_size.max(1) {
+            query_stats::leaf_scan();
+            T::leaf_distances(
+                params.point,
+                subtree.len(),
+                |position| subtree[position],
+                |_, dst| {
+                    if params.boundary.contains(dst, params.r_max)
+                        && !params.boundary.contains(dst, params.r_min)
+                    {
+                        count += 1;
+                    }
+                },
+            );
+        } else {
+            let (split_index, row, splits1, splits2) = splits.split(subtree.len());
+            let split_point = splits.split_point(subtree, split_index);
+
+            let dst = distance(split_point, params.point);
+            if params.boundary.contains(dst, params.r_max)
+                && !params.boundary.contains(dst, params.r_min)
+            {
+                count += 1;
+            }
+
+            let subtree1 = &subtree[..split_index];
+            let subtree2 = &subtree[(split_index + 1)..];
+            if params.point[row] <= split_point[row] {
+                count += Self::dispatch_count_shell_recursive_on_subtrees(
+                    subtree1,
+                    subtree2,
+                    split_point,
+                    params,
+                    subtree_distance,
+                    bounds,
+                    splits1,
+                    splits2,
+                    row,
+                );
+            } else if params.point[row] > split_point[row] {
+                count += Self::dispatch_count_shell_recursive_on_subtrees(
+                    subtree2,
+                    subtree1,
+                    split_point,
+                    params,
+                    subtree_distance,
+                    bounds,
+                    splits2,
+                    splits1,
+                    row,
+                );
+            }
+        }
+        count
+    }
+}
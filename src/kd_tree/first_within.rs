@@ -0,0 +1,154 @@
+use super::GenericKdTree;
+use crate::{
+    distance, query_stats, top_levels::TopSplits, Boundary, Coordinate, NeighbourhoodParams,
+};
+use num_traits::Zero;
+
+impl<T: Coordinate, const N: usize, D: AsRef<[[T; N]]>> GenericKdTree<'_, T, N, D> {
+    /// Returns true if at least one point lies within the ball of radius epsilon
+    /// around p. Stops at the first point found.
+    pub fn any_within(&self, point: &[T; N], epsilon: T::Distance, boundary: Boundary) -> bool {
+        self.first_within(point, epsilon, boundary).is_some()
+    }
+
+    /// Returns the index of some point within the ball of radius epsilon around p, or
+    /// `None` if there is no such point. Stops at the first point found, which is not
+    /// necessarily the closest one. The index can be used together with [Self::data]
+    /// to retrieve the point.
+    pub fn first_within(
+        &self,
+        point: &[T; N],
+        epsilon: T::Distance,
+        boundary: Boundary,
+    ) -> Option<usize> {
+        let mut subtree_distance = [T::Distance::zero(); N];
+
+        let params = NeighbourhoodParams {
+            point,
+            epsilon,
+            boundary,
+            brute_force_size: self.leaf_scan_size(),
+        };
+
+        Self::find_first_within_recursive(
+            0,
+            self.data(),
+            &params,
+            &mut subtree_distance,
+            self.top_splits(),
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[inline]
+    fn dispatch_find_first_within_recursive_on_subtrees(
+        subtree1_offset: usize,
+        subtree1: &[[T; N]],
+        subtree2_offset: usize,
+        subtree2: &[[T; N]],
+        split_point: &[T; N],
+        params: &NeighbourhoodParams<T, N>,
+        subtree_distance: &mut [T::Distance; N],
+        splits1: TopSplits<T, N>,
+        splits2: TopSplits<T, N>,
+        row: usize,
+    ) -> Option<usize> {
+        let found = Self::find_first_within_recursive(
+            subtree1_offset,
+            subtree1,
+            params,
+            subtree_distance,
+            splits1,
+        );
+        if found.is_some() {
+            return found;
+        }
+
+        let mut found = None;
+        let row_value = subtree_distance[row];
+        subtree_distance[row] = params.point[row].offset(split_point[row]);
+        if params
+            .boundary
+            .contains(T::norm(subtree_distance), params.epsilon)
+        {
+            found = Self::find_first_within_recursive(
+                subtree2_offset,
+                subtree2,
+                params,
+                subtree_distance,
+                splits2,
+            );
+        } else {
+            query_stats::subtree_pruned();
+        }
+        subtree_distance[row] = row_value;
+        found
+    }
+
+    fn find_first_within_recursive(
+        subtree_offset: usize,
+        subtree: &[[T; N]],
+        params: &NeighbourhoodParams<T, N>,
+        subtree_distance: &mut [T::Distance; N],
+        splits: TopSplits<T, N>,
+    ) -> Option<usize> {
+        let _node = query_stats::enter_node();
+        if subtree.len() <= params.brute_force_size.max(1) {
+            query_stats::leaf_scan();
+            let mut first = None;
+            T::leaf_distances(
+                params.point,
+                subtree.len(),
+                |position| subtree[position],
+                |position, dst| {
+                    if first.is_none() && params.boundary.contains(dst, params.epsilon) {
+                        first = Some(subtree_offset + position);
+                    }
+                },
+            );
+            first
+        } else {
+            let (split_index, row, splits1, splits2) = splits.split(subtree.len());
+            let split_point = splits.split_point(subtree, split_index);
+
+            if params
+                .boundary
+                .contains(distance(split_point, params.point), params.epsilon)
+            {
+                return Some(subtree_offset + split_index);
+            }
+
+            let subtree1 = &subtree[..split_index];
+            let subtree2 = &subtree[(split_index + 1)..];
+            if params.point[row] <= split_point[row] {
+                Self::dispatch_find_first_within_recursive_on_subtrees(
+                    subtree_offset,
+                    subtree1,
+                    subtree_offset + split_index + 1,
+                    subtree2,
+                    split_point,
+                    params,
+                    subtree_distance,
+                    splits1,
+                    splits2,
+                    row,
+                )
+            } else if params.point[row] > split_point[row] {
+                Self::dispatch_find_first_within_recursive_on_subtrees(
+                    subtree_offset + split_index + 1,
+                    subtree2,
+                    subtree_offset,
+                    subtree1,
+                    split_point,
+                    params,
+                    subtree_distance,
+                    splits2,
+                    splits1,
+                    row,
+                )
+            } else {
+                None
+            }
+        }
+    }
+}
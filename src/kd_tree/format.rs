@@ -0,0 +1,126 @@
+use super::{GenericKdTree, KdTreeView};
+use crate::{
+    format::{
+        ByteOrder, FormatError, FormatScalar, Header, HEADER_SIZE, LAYOUT_AXES, LAYOUT_BUCKET_LEAF,
+        LAYOUT_EXPLICIT, LAYOUT_ROUND_ROBIN,
+    },
+    split::{ExplicitSplit, SplitLayout},
+    ValidationError,
+};
+use alloc::{borrow::Cow, vec::Vec};
+use core::mem::{align_of, size_of, size_of_val};
+
+impl<T: FormatScalar, const N: usize, D: AsRef<[[T; N]]>> GenericKdTree<'_, T, N, D> {
+    /// Writes the tree in the binary format described in [crate::format], which can be
+    /// used in place by [KdTreeView::from_bytes].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let (layout, axes, splits): (u8, &[u8], &[ExplicitSplit]) = match &self.layout {
+            SplitLayout::RoundRobin => (LAYOUT_ROUND_ROBIN, &[], &[]),
+            SplitLayout::Axes(axes) => (LAYOUT_AXES, axes, &[]),
+            SplitLayout::BucketLeaf { axes, .. } => (LAYOUT_BUCKET_LEAF, axes, &[]),
+            SplitLayout::Explicit { axes, splits } => (LAYOUT_EXPLICIT, axes, splits),
+        };
+
+        let mut bytes = Vec::with_capacity(
+            HEADER_SIZE + self.len() * size_of::<[T; N]>() + axes.len() + size_of_val(splits) + 8,
+        );
+        let header = Header {
+            layout,
+            len: self.len() as u64,
+            brute_force_size: self.brute_force_size as u64,
+            leaf_size: self.leaf_size as u64,
+            stored_splits: splits.len() as u64,
+        };
+        header.write::<T, N>(&mut bytes, ByteOrder::Native);
+
+        for point in self.data() {
+            for coordinate in point {
+                coordinate.extend_ne_bytes(&mut bytes);
+            }
+        }
+        bytes.extend_from_slice(axes);
+        if layout == LAYOUT_EXPLICIT {
+            bytes.resize(bytes.len().next_multiple_of(8), 0);
+            for split in splits {
+                bytes.extend_from_slice(&split.split_index.to_ne_bytes());
+                bytes.extend_from_slice(&split.left_splits.to_ne_bytes());
+            }
+        }
+        bytes
+    }
+}
+
+impl<'a, T: FormatScalar, const N: usize> KdTreeView<'a, T, N> {
+    /// Uses bytes written by [GenericKdTree::to_bytes] in place. `bytes` must be aligned
+    /// for `T`, and for `u32` if the tree stores explicit split positions, which holds
+    /// for memory maps. The header, the length and the range of the stored splits are
+    /// checked, but not the partitioning of the points, use [Self::validate] before
+    /// querying untrusted bytes.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, FormatError> {
+        let header = Header::read::<T, N>(bytes, ByteOrder::Native)?;
+        let len = usize::try_from(header.len).map_err(|_| FormatError::Length)?;
+        let brute_force_size = usize::try_from(header.brute_force_size).unwrap_or(usize::MAX);
+        let leaf_size = usize::try_from(header.leaf_size).unwrap_or(usize::MAX);
+        let points_end = len
+            .checked_mul(size_of::<[T; N]>())
+            .and_then(|size| size.checked_add(HEADER_SIZE))
+            .ok_or(FormatError::Length)?;
+        let axes_end = points_end.checked_add(len).ok_or(FormatError::Length)?;
+        let splits_start = axes_end.next_multiple_of(8);
+        let stored_splits =
+            usize::try_from(header.stored_splits).map_err(|_| FormatError::Length)?;
+
+        let layout = header.layout;
+        let expected_len = match layout {
+            LAYOUT_ROUND_ROBIN => Some(points_end),
+            LAYOUT_AXES | LAYOUT_BUCKET_LEAF => Some(axes_end),
+            LAYOUT_EXPLICIT => stored_splits
+                .checked_mul(size_of::<ExplicitSplit>())
+                .and_then(|size| size.checked_add(splits_start)),
+            _ => return Err(FormatError::Layout(layout)),
+        };
+        if expected_len != Some(bytes.len()) {
+            return Err(FormatError::Length);
+        }
+
+        let points = &bytes[HEADER_SIZE..points_end];
+        if points.as_ptr() as usize % align_of::<T>() != 0 {
+            return Err(FormatError::Alignment);
+        }
+        // SAFETY: The bytes cover `len` points and are aligned for `T`. `[T; N]` has no
+        // padding and every bit pattern is a valid `T`, see [FormatScalar].
+        let data = unsafe { core::slice::from_raw_parts(points.as_ptr().cast::<[T; N]>(), len) };
+
+        let layout = match layout {
+            LAYOUT_ROUND_ROBIN => SplitLayout::RoundRobin,
+            LAYOUT_AXES => SplitLayout::Axes(Cow::Borrowed(&bytes[points_end..axes_end])),
+            LAYOUT_BUCKET_LEAF => SplitLayout::BucketLeaf {
+                axes: Cow::Borrowed(&bytes[points_end..axes_end]),
+                leaf_size,
+            },
+            _ => {
+                let splits = &bytes[splits_start..];
+                if splits.as_ptr() as usize % align_of::<ExplicitSplit>() != 0 {
+                    return Err(FormatError::Alignment);
+                }
+                // SAFETY: The bytes cover `stored_splits` splits and are aligned for them.
+                // `ExplicitSplit` consists of two `u32` without padding, so every bit
+                // pattern is valid.
+                let splits = unsafe {
+                    core::slice::from_raw_parts(
+                        splits.as_ptr().cast::<ExplicitSplit>(),
+                        stored_splits,
+                    )
+                };
+                SplitLayout::Explicit {
+                    axes: Cow::Borrowed(&bytes[points_end..axes_end]),
+                    splits: Cow::Borrowed(splits),
+                }
+            }
+        };
+        if let Err(ValidationError::Layout { position }) = layout.check::<N>(len, leaf_size) {
+            return Err(FormatError::Splits(position));
+        }
+        Ok(Self::from_parts(data, brute_force_size, layout, leaf_size))
+    }
+}
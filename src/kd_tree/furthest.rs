@@ -0,0 +1,153 @@
+use super::GenericKdTree;
+use crate::{
+    distance, max_box_distance, query_stats, top_levels::TopSplits, unbounded_box, Coordinate,
+    KnnParams,
+};
+use alloc::{vec, vec::Vec};
+
+impl<T: Coordinate, const N: usize, D: AsRef<[[T; N]]>> GenericKdTree<'_, T, N, D> {
+    /// Returns the point furthest away from `point` together with its distance, or
+    /// `None` if the KdTree is empty.
+    pub fn furthest<'a>(&'a self, point: &[T; N]) -> Option<(T::Distance, &'a [T; N])> {
+        self.k_furthest(point, 1).pop()
+    }
+
+    /// Returns the k points furthest away from `point`, sorted by descending distance.
+    pub fn k_furthest<'a>(&'a self, point: &[T; N], k: usize) -> Vec<(T::Distance, &'a [T; N])> {
+        if k == 0 {
+            return vec![];
+        }
+        let mut bounds = unbounded_box();
+        let mut result = Vec::with_capacity(k);
+
+        let params = KnnParams {
+            point,
+            k: core::num::NonZero::new(k).unwrap(),
+            brute_force_size: self.leaf_scan_size(),
+        };
+        Self::find_k_furthest_recursive(
+            self.data(),
+            &params,
+            &mut bounds,
+            &mut result,
+            self.top_splits(),
+        );
+
+        result
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[inline]
+    fn dispatch_find_k_furthest_recursive_on_subtrees<'a>(
+        subtree1: &'a [[T; N]],
+        subtree2: &'a [[T; N]],
+        split_point: &[T; N],
+        params: &KnnParams<T, N>,
+        bounds: &mut [[T; N]; 2],
+        result: &mut Vec<(T::Distance, &'a [T; N])>,
+        splits1: TopSplits<T, N>,
+        splits2: TopSplits<T, N>,
+        row: usize,
+    ) {
+        let (far_bound, near_bound) = if params.point[row] <= split_point[row] {
+            (0, 1)
+        } else {
+            (1, 0)
+        };
+
+        let bound_value = bounds[far_bound][row];
+        bounds[far_bound][row] = split_point[row];
+        Self::find_k_furthest_recursive(subtree1, params, bounds, result, splits1);
+        bounds[far_bound][row] = bound_value;
+
+        let bound_value = bounds[near_bound][row];
+        bounds[near_bound][row] = split_point[row];
+        Self::find_k_furthest_recursive(subtree2, params, bounds, result, splits2);
+        bounds[near_bound][row] = bound_value;
+    }
+
+    fn find_k_furthest_recursive<'a>(
+        subtree: &'a [[T; N]],
+        params: &KnnParams<T, N>,
+        bounds: &mut [[T; N]; 2],
+        result: &mut Vec<(T::Distance, &'a [T; N])>,
+        splits: TopSplits<T, N>,
+    ) {
+        let _node = query_stats::enter_node();
+        if result.len() == params.k.get()
+            && max_box_distance(params.point, bounds) <= result.last().unwrap().0
+        {
+            query_stats::subtree_pruned();
+            return;
+        }
+
+        if subtree.len() <= params.brute_force_size.max(1) {
+            query_stats::leaf_scan();
+            T::leaf_distances(
+                params.point,
+                subtree.len(),
+                |position| subtree[position],
+                |position, dst| {
+                    Self::k_furthest_try_insert(params, result, dst, &subtree[position])
+                },
+            );
+        } else {
+            let (split_index, row, splits1, splits2) = splits.split(subtree.len());
+            let split_point = splits.split_point(subtree, split_index);
+
+            let dst = distance(split_point, params.point);
+            Self::k_furthest_try_insert(params, result, dst, &subtree[split_index]);
+
+            let subtree1 = &subtree[..split_index];
+            let subtree2 = &subtree[(split_index + 1)..];
+            if params.point[row] <= split_point[row] {
+                Self::dispatch_find_k_furthest_recursive_on_subtrees(
+                    subtree2,
+                    subtree1,
+                    split_point,
+                    params,
+                    bounds,
+                    result,
+                    splits2,
+                    splits1,
+                    row,
+                );
+            } else if params.point[row] > split_point[row] {
+                Self::dispatch_find_k_furthest_recursive_on_subtrees(
+                    subtree1,
+                    subtree2,
+                    split_point,
+                    params,
+                    bounds,
+                    result,
+                    splits1,
+                    splits2,
+                    row,
+                );
+            }
+        }
+    }
+
+    #[inline]
+    fn k_furthest_try_insert<'a>(
+        params: &KnnParams<T, N>,
+        result: &mut Vec<(T::Distance, &'a [T; N])>,
+        dst: T::Distance,
+        point: &'a [T; N],
+    ) {
+        if result.len() < params.k.get() {
+            let pos = result
+                .iter()
+                .position(|p| dst > p.0)
+                .unwrap_or(result.len());
+            result.insert(pos, (dst, point));
+        } else if dst > result.last().unwrap().0 {
+            let pos = result
+                .iter()
+                .position(|p| dst > p.0)
+                .unwrap_or(result.len());
+            result.insert(pos, (dst, point));
+            let _ = result.pop();
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use super::GenericKdTree;
+use crate::{
+    distance, max_box_distance, query_stats, top_levels::TopSplits, unbounded_box, Coordinate,
+    KnnParams,
+};
+use alloc::{vec, vec::Vec};
+
+impl<T: Coordinate, const N: usize, D: AsRef<[[T; N]]>> GenericKdTree<'_, T, N, D> {
+    /// Returns the index of the point furthest away from `point` together with its
+    /// distance, or `None` if the KdTree is empty.
+    pub fn furthest_by_index(&self, point: &[T; N]) -> Option<(T::Distance, usize)> {
+        self.k_furthest_by_index(point, 1).pop()
+    }
+
+    /// Returns the index of the k points furthest away from `point`, sorted by
+    /// descending distance.
+    pub fn k_furthest_by_index(&self, point: &[T; N], k: usize) -> Vec<(T::Distance, usize)> {
+        if k == 0 {
+            return vec![];
+        }
+        let mut bounds = unbounded_box();
+        let mut result = Vec::with_capacity(k);
+
+        let params = KnnParams {
+            point,
+            k: core::num::NonZero::new(k).unwrap(),
+            brute_force_size: self.leaf_scan_size(),
+        };
+        Self::find_k_furthest_by_index_recursive(
+            0,
+            self.data(),
+            &params,
+            &mut bounds,
+            &mut result,
+            self.top_splits(),
+        );
+
+        result
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[inline]
+    fn dispatch_find_k_furthest_by_index_recursive_on_subtrees(
+        subtree1_offset: usize,
+        subtree1: &[[T; N]],
+        subtree2_offset: usize,
+        subtree2: &[[T; N]],
+        split_point: &[T; N],
+        params: &KnnParams<T, N>,
+        bounds: &mut [[T; N]; 2],
+        result: &mut Vec<(T::Distance, usize)>,
+        splits1: TopSplits<T, N>,
+        splits2: TopSplits<T, N>,
+        row: usize,
+    ) {
+        let (far_bound, near_bound) = if params.point[row] <= split_point[row] {
+            (0, 1)
+        } else {
+            (1, 0)
+        };
+
+        let bound_value = bounds[far_bound][row];
+        bounds[far_bound][row] = split_point[row];
+        Self::find_k_furthest_by_index_recursive(
+            subtree1_offset,
+            subtree1,
+            params,
+            bounds,
+            result,
+            splits1,
+        );
+        bounds[far_bound][row] = bound_value;
+
+        let bound_value = bounds[near_bound][row];
+        bounds[near_bound][row] = split_point[row];
+        Self::find_k_furthest_by_index_recursive(
+            subtree2_offset,
+            subtree2,
+            params,
+            bounds,
+            result,
+            splits2,
+        );
+        bounds[near_bound][row] = bound_value;
+    }
+
+    fn find_k_furthest_by_index_recursive(
+        subtree_offset: usize,
+        subtree: &[[T; N]],
+        params: &KnnParams<T, N>,
+        bounds: &mut [[T; N]; 2],
+        result: &mut Vec<(T::Distance, usize)>,
+        splits: TopSplits<T, N>,
+    ) {
+        let _node = query_stats::enter_node();
+        if result.len() == params.k.get()
+            && max_box_distance(params.point, bounds) <= result.last().unwrap().0
+        {
+            query_stats::subtree_pruned();
+            return;
+        }
+
+        if subtree.len() <= params.brute_force_size.max(1) {
+            query_stats::leaf_scan();
+            T::leaf_distances(
+                params.point,
+                subtree.len(),
+                |position| subtree[position],
+                |position, dst| {
+                    Self::k_furthest_try_insert_index(
+                        params,
+                        result,
+                        dst,
+                        subtree_offset + position,
+                    )
+                },
+            );
+        } else {
+            let (split_index, row, splits1, splits2) = splits.split(subtree.len());
+            let split_point = splits.split_point(subtree, split_index);
+
+            Self::k_furthest_try_insert_index(
+                params,
+                result,
+                distance(split_point, params.point),
+                subtree_offset + split_index,
+            );
+
+            let subtree1 = &subtree[..split_index];
+            let subtree2 = &subtree[(split_index + 1)..];
+            if params.point[row] <= split_point[row] {
+                Self::dispatch_find_k_furthest_by_index_recursive_on_subtrees(
+                    subtree_offset + split_index + 1,
+                    subtree2,
+                    subtree_offset,
+                    subtree1,
+                    split_point,
+                    params,
+                    bounds,
+                    result,
+                    splits2,
+                    splits1,
+                    row,
+                );
+            } else if params.point[row] > split_point[row] {
+                Self::dispatch_find_k_furthest_by_index_recursive_on_subtrees(
+                    subtree_offset,
+                    subtree1,
+                    subtree_offset + split_index + 1,
+                    subtree2,
+                    split_point,
+                    params,
+                    bounds,
+                    result,
+                    splits1,
+                    splits2,
+                    row,
+                );
+            }
+        }
+    }
+
+    #[inline]
+    fn k_furthest_try_insert_index(
+        params: &KnnParams<T, N>,
+        result: &mut Vec<(T::Distance, usize)>,
+        dst: T::Distance,
+        index: usize,
+    ) {
+        if result.len() < params.k.get() {
+            let pos = result
+                .iter()
+                .position(|p| dst > p.0)
+                .unwrap_or(result.len());
+            result.insert(pos, (dst, index));
+        } else if dst > result.last().unwrap().0 {
+            let pos = result
+                .iter()
+                .position(|p| dst > p.0)
+                .unwrap_or(result.len());
+            result.insert(pos, (dst, index));
+            let _ = result.pop();
+        }
+    }
+}
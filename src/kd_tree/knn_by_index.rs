@@ -1,28 +1,32 @@
-use super::KdTree;
-use crate::{distance, norm, KnnParams};
+use super::GenericKdTree;
+use crate::{
+    bounds::NodeBoxes, distance, min_box_distance, query_stats, top_levels::TopSplits, Coordinate,
+    KnnParams,
+};
 use alloc::{vec, vec::Vec};
-use num_traits::Float;
+use num_traits::Zero;
 
-impl<T: Float + Clone, const N: usize> KdTree<T, N> {
-    pub fn knn_by_index(&self, point: &[T; N], k: usize) -> Vec<(T, usize)> {
+impl<T: Coordinate, const N: usize, D: AsRef<[[T; N]]>> GenericKdTree<'_, T, N, D> {
+    pub fn knn_by_index(&self, point: &[T; N], k: usize) -> Vec<(T::Distance, usize)> {
         if k == 0 {
             return vec![];
         }
-        let mut subtree_distance = [T::zero(); N];
+        let mut subtree_distance = [T::Distance::zero(); N];
         let mut result = Vec::with_capacity(k);
 
         let params = KnnParams {
             point,
             k: core::num::NonZero::new(k).unwrap(),
-            brute_force_size: self.brute_force_size,
+            brute_force_size: self.leaf_scan_size(),
         };
         Self::find_knn_by_index_recursive(
             0,
-            &self.data,
+            self.data(),
             &params,
             &mut subtree_distance,
             &mut result,
-            0,
+            self.top_splits(),
+            self.node_boxes(),
         );
 
         result
@@ -37,23 +41,27 @@ impl<T: Float + Clone, const N: usize> KdTree<T, N> {
         subtree2: &'a [[T; N]],
         split_point: &[T; N],
         params: &KnnParams<T, N>,
-        subtree_distance: &mut [T; N],
-        result: &mut Vec<(T, usize)>,
+        subtree_distance: &mut [T::Distance; N],
+        result: &mut Vec<(T::Distance, usize)>,
+        splits1: TopSplits<T, N>,
+        splits2: TopSplits<T, N>,
+        boxes1: NodeBoxes<T, N>,
+        boxes2: NodeBoxes<T, N>,
         row: usize,
     ) {
-        let next_row = (row + 1) % N;
         Self::find_knn_by_index_recursive(
             subtree1_offset,
             subtree1,
             params,
             subtree_distance,
             result,
-            next_row,
+            splits1,
+            boxes1,
         );
 
         let row_value = subtree_distance[row];
-        subtree_distance[row] = params.point[row] - split_point[row];
-        let dst = norm(subtree_distance);
+        subtree_distance[row] = params.point[row].offset(split_point[row]);
+        let dst = T::norm(subtree_distance);
         if result.len() < params.k.get() || dst < result.last().unwrap().0 {
             Self::find_knn_by_index_recursive(
                 subtree2_offset,
@@ -61,8 +69,11 @@ impl<T: Float + Clone, const N: usize> KdTree<T, N> {
                 params,
                 subtree_distance,
                 result,
-                next_row,
+                splits2,
+                boxes2,
             );
+        } else {
+            query_stats::subtree_pruned();
         }
         subtree_distance[row] = row_value;
     }
@@ -71,17 +82,34 @@ impl<T: Float + Clone, const N: usize> KdTree<T, N> {
         subtree_offset: usize,
         subtree: &[[T; N]],
         params: &KnnParams<T, N>,
-        subtree_distance: &mut [T; N],
-        result: &mut Vec<(T, usize)>,
-        row: usize,
+        subtree_distance: &mut [T::Distance; N],
+        result: &mut Vec<(T::Distance, usize)>,
+        splits: TopSplits<T, N>,
+        boxes: NodeBoxes<T, N>,
     ) {
-        if subtree.len() <= params.brute_force_size.max(1) {
-            for (index, pt) in subtree.iter().enumerate() {
-                Self::knn_try_insert_index(params, result, pt, subtree_offset + index);
+        let _node = query_stats::enter_node();
+        if let Some(bounds) = boxes.bounds(subtree.len()) {
+            if result.len() == params.k.get()
+                && min_box_distance(params.point, bounds) >= result.last().unwrap().0
+            {
+                query_stats::subtree_pruned();
+                return;
             }
+        }
+        if subtree.len() <= params.brute_force_size.max(1) {
+            query_stats::leaf_scan();
+            T::leaf_distances(
+                params.point,
+                subtree.len(),
+                |position| subtree[position],
+                |position, dst| {
+                    Self::knn_try_insert_index(params, result, dst, subtree_offset + position)
+                },
+            );
         } else {
-            let split_index = subtree.len() / 2;
-            let split_point = &subtree[split_index];
+            let (split_index, row, splits1, splits2) = splits.split(subtree.len());
+            let (boxes1, boxes2) = boxes.split(subtree.len());
+            let split_point = splits.split_point(subtree, split_index);
 
             let subtree1 = &subtree[..split_index];
             let subtree2 = &subtree[(split_index + 1)..];
@@ -95,6 +123,10 @@ impl<T: Float + Clone, const N: usize> KdTree<T, N> {
                     params,
                     subtree_distance,
                     result,
+                    splits1,
+                    splits2,
+                    boxes1,
+                    boxes2,
                     row,
                 );
             } else if params.point[row] > split_point[row] {
@@ -107,22 +139,30 @@ impl<T: Float + Clone, const N: usize> KdTree<T, N> {
                     params,
                     subtree_distance,
                     result,
+                    splits2,
+                    splits1,
+                    boxes2,
+                    boxes1,
                     row,
                 );
             }
 
-            Self::knn_try_insert_index(params, result, split_point, subtree_offset + split_index);
+            Self::knn_try_insert_index(
+                params,
+                result,
+                distance(split_point, params.point),
+                subtree_offset + split_index,
+            );
         }
     }
 
     #[inline]
     fn knn_try_insert_index(
         params: &KnnParams<T, N>,
-        result: &mut Vec<(T, usize)>,
-        point: &[T; N],
+        result: &mut Vec<(T::Distance, usize)>,
+        dst: T::Distance,
         index: usize,
     ) {
-        let dst = distance(point, params.point);
         if result.is_empty() {
             result.push((dst, index));
         } else if result.len() < params.k.get() {
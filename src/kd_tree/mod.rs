@@ -1,28 +1,82 @@
 use alloc::vec::Vec;
+use core::marker::PhantomData;
 use num_traits::Float;
 
+use crate::{
+    bounds::{BoundingBoxes, NodeBoxes},
+    knn_radii::TreeId,
+    split::{SplitLayout, Splits},
+    top_levels::{TopLevels, TopSplits},
+    Coordinate, KdTreeBuilder, SplitAxis,
+};
+
 pub mod count_neighbourhood;
+pub mod count_shell;
+pub mod first_within;
+pub mod format;
+pub mod furthest;
+pub mod furthest_by_index;
 pub mod knn;
 pub mod knn_by_index;
+pub mod nearest;
+pub mod nearest_by_index;
 pub mod neighbourhood;
 pub mod neighbourhood_by_index;
+pub mod reverse_knn;
+pub mod reverse_knn_by_index;
+pub mod shell;
+pub mod shell_by_index;
+pub mod stats;
+#[cfg(feature = "std")]
+pub mod tune_brute_force_size;
 
-pub struct KdTree<T, const N: usize> {
-    data: Vec<[T; N]>,
+/// A K-d tree stored in the order of its implicit layout. The points are held in `D`,
+/// which is a `Vec` for the owning [KdTree] and a slice for the borrowing [KdTreeView].
+pub struct GenericKdTree<'a, T, const N: usize, D> {
+    pub(crate) data: D,
 
     /// Determines the size at which the KdIndexTree will switch
     /// to a brute force approach instead of further recursing
     /// the tree.
     pub brute_force_size: usize,
+
+    /// Split positions and axes of the nodes.
+    pub(crate) layout: SplitLayout<'a>,
+
+    /// Subtrees of up to `leaf_size` points are not split during construction.
+    pub(crate) leaf_size: usize,
+
+    /// Bounding boxes of the larger subtrees, see [crate::KdTreeBuilder::bounding_boxes].
+    pub(crate) boxes: BoundingBoxes<T, N>,
+
+    /// Contiguous copy of the top levels, see [crate::KdTreeBuilder::top_levels].
+    pub(crate) top_levels: TopLevels<T, N>,
+
+    /// Configuration the tree was built with, used to rebuild it on [Extend::extend].
+    pub(crate) builder: KdTreeBuilder,
+
+    /// Rebuilds the tree with `builder`. Only float trees can be built by a
+    /// [KdTreeBuilder], all others are rebuilt like [KdTree::new].
+    pub(crate) rebuild: fn(&KdTreeBuilder, Vec<[T; N]>) -> KdTree<T, N>,
+
+    /// Identifies the order of the points for [crate::KnnRadii].
+    pub(crate) id: TreeId,
+
+    _marker: PhantomData<T>,
 }
 
-impl<T: Float + Clone, const N: usize> KdTree<T, N> {
-    pub const DEFAULT_BRUTE_FORCE_SIZE: usize = if core::mem::size_of::<T>() >= 64 {
-        25
-    } else {
-        34
-    };
+/// A K-d tree owning its points.
+pub type KdTree<T, const N: usize> = GenericKdTree<'static, T, N, Vec<[T; N]>>;
 
+/// A K-d tree borrowing its points and split information, e.g. from a memory map. See
+/// [KdTreeView::from_bytes].
+pub type KdTreeView<'a, T, const N: usize> = GenericKdTree<'a, T, N, &'a [[T; N]]>;
+
+/// A K-d tree built in place on a mutable slice owned by the caller, see
+/// [KdTree::from_slice_mut].
+pub type KdTreeMut<'a, T, const N: usize> = GenericKdTree<'a, T, N, &'a mut [[T; N]]>;
+
+impl<T: Coordinate, const N: usize> KdTree<T, N> {
     fn select_median_with_row_recursive(slice: &mut [[T; N]], row: usize) {
         let split_index = slice.len() / 2;
         slice.select_nth_unstable_by(split_index, |lhs, rhs| {
@@ -46,11 +100,37 @@ impl<T: Float + Clone, const N: usize> KdTree<T, N> {
 
     /// Create a new K-d Tree.
     pub fn new(mut data: Vec<[T; N]>) -> Self {
-        Self::select_median_with_row_recursive(&mut data, 0);
-        Self {
+        if !data.is_empty() {
+            Self::select_median_with_row_recursive(&mut data, 0);
+        }
+        Self::from_parts(
             data,
-            brute_force_size: Self::DEFAULT_BRUTE_FORCE_SIZE,
+            Self::DEFAULT_BRUTE_FORCE_SIZE,
+            SplitLayout::RoundRobin,
+            1,
+        )
+    }
+
+    /// Create a new K-d Tree on `data` by reordering it in place. The data is neither
+    /// copied nor moved, which allows building trees in arenas or memory maps.
+    pub fn from_slice_mut(data: &mut [[T; N]]) -> KdTreeMut<'_, T, N> {
+        if !data.is_empty() {
+            Self::select_median_with_row_recursive(data, 0);
         }
+        GenericKdTree::from_parts(
+            data,
+            Self::DEFAULT_BRUTE_FORCE_SIZE,
+            SplitLayout::RoundRobin,
+            1,
+        )
+    }
+
+    /// Create a new K-d Tree whose split axes are chosen according to `split_axis`.
+    pub fn with_split_axis(data: Vec<[T; N]>, split_axis: SplitAxis) -> Self
+    where
+        T: Float,
+    {
+        KdTreeBuilder::new().split_axis(split_axis).build(data)
     }
 
     /// Create a new K-d Tree and sets the `brute_force_size`.
@@ -59,35 +139,149 @@ impl<T: Float + Clone, const N: usize> KdTree<T, N> {
         self_.brute_force_size = brute_force_size;
         self_
     }
+}
+
+impl<T: Coordinate, const N: usize> FromIterator<[T; N]> for KdTree<T, N> {
+    /// Collects the points and builds the tree like [KdTree::new]. Iterators with a known
+    /// length allocate the points once.
+    fn from_iter<I: IntoIterator<Item = [T; N]>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+impl<T: Coordinate, const N: usize> Extend<[T; N]> for KdTree<T, N> {
+    /// Appends the points and rebuilds the tree with the [KdTreeBuilder] configuration it
+    /// was built with, keeping the `brute_force_size`.
+    fn extend<I: IntoIterator<Item = [T; N]>>(&mut self, iter: I) {
+        let mut data = core::mem::take(&mut self.data);
+        data.extend(iter);
+        let brute_force_size = self.brute_force_size;
+        *self = (self.rebuild)(&self.builder, data);
+        self.brute_force_size = brute_force_size;
+    }
+}
+
+impl<'a, T: Coordinate, const N: usize> KdTreeView<'a, T, N> {
+    /// Creates a view of points that are already in the order [KdTree::new] puts them
+    /// in, e.g. the [KdTree::data] of such a tree. Use [Self::validate] to check the
+    /// order of untrusted data.
+    pub fn from_partitioned(data: &'a [[T; N]], brute_force_size: usize) -> Self {
+        Self::from_parts(data, brute_force_size, SplitLayout::RoundRobin, 1)
+    }
+}
+
+impl<'a, T: Coordinate, const N: usize, D: AsRef<[[T; N]]>> GenericKdTree<'a, T, N, D> {
+    pub const DEFAULT_BRUTE_FORCE_SIZE: usize = if core::mem::size_of::<T>() >= 64 {
+        25
+    } else {
+        34
+    };
+
+    pub(crate) fn from_parts(
+        data: D,
+        brute_force_size: usize,
+        layout: SplitLayout<'a>,
+        leaf_size: usize,
+    ) -> Self {
+        Self {
+            data,
+            brute_force_size,
+            builder: KdTreeBuilder::for_layout(&layout, leaf_size),
+            rebuild: |_, data| KdTree::new(data),
+            layout,
+            leaf_size,
+            boxes: BoundingBoxes::none(),
+            top_levels: TopLevels::none(),
+            id: TreeId::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns a view borrowing the points and the split information of the tree.
+    /// Bounding boxes and top levels, if any, are copied.
+    pub fn view(&self) -> KdTreeView<'_, T, N> {
+        let mut view = GenericKdTree::from_parts(
+            self.data(),
+            self.brute_force_size,
+            self.layout.borrowed(),
+            self.leaf_size,
+        );
+        view.boxes = self.boxes.clone();
+        view.top_levels = self.top_levels.clone();
+        view.builder = self.builder;
+        view.rebuild = self.rebuild;
+        view.id = self.id;
+        view
+    }
 
     /// Number of points in the KdTree.
     pub fn len(&self) -> usize {
-        self.data.len()
+        self.data().len()
     }
 
     /// Returns true if the Kd-tree is empty.
     pub fn is_empty(&self) -> bool {
-        self.data.is_empty()
+        self.data().is_empty()
     }
 
     /// Returns a read-only reference to the data.
     pub fn data(&self) -> &[[T; N]] {
-        self.data.as_slice()
+        self.data.as_ref()
+    }
+
+    /// Number of points up to which the queries scan a subtree by brute force.
+    fn leaf_scan_size(&self) -> usize {
+        self.brute_force_size.max(self.leaf_size)
+    }
+
+    fn splits(&self) -> Splits<'_> {
+        self.layout.splits()
+    }
+
+    fn node_boxes(&self) -> NodeBoxes<'_, T, N> {
+        self.boxes.node_boxes()
+    }
+
+    fn top_splits(&self) -> TopSplits<'_, T, N> {
+        self.top_levels.splits(self.splits())
+    }
+
+    /// Stores the bounding boxes of all subtrees larger than the current leaf scan size.
+    pub(crate) fn compute_bounding_boxes(&mut self) {
+        self.boxes = BoundingBoxes::new(
+            self.len(),
+            &|position| self.data()[position],
+            self.splits(),
+            self.leaf_scan_size(),
+        );
+    }
+
+    /// Stores a contiguous copy of the split points of the top `levels` levels.
+    pub(crate) fn compute_top_levels(&mut self, levels: usize) {
+        self.top_levels = TopLevels::new(
+            self.len(),
+            &|position| self.data()[position],
+            &|position| position,
+            self.splits(),
+            self.leaf_size,
+            levels,
+        );
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use alloc::vec;
+    use alloc::{vec, vec::Vec};
 
     use super::KdTree;
-    use crate::distance;
-
-    #[test]
-    fn simple_neighbourhood_query_test() {
-        let mut data = vec![];
+    use crate::{
+        distance, scattered_points, split::SplitLayout, Boundary, KdTreeBuilder, SplitAxis,
+    };
 
+    /// Points of a 5x5x5 grid with spacing 1 around the origin.
+    fn grid_points() -> Vec<[f64; 3]> {
         let line = [-2.0, -1.0, 0.0, 1.0, 2.0];
+        let mut data = vec![];
         for x in line {
             for y in line {
                 for z in line {
@@ -95,15 +289,691 @@ mod tests {
                 }
             }
         }
+        data
+    }
+
+    #[test]
+    fn simple_neighbourhood_query_test() {
+        let data = grid_points();
 
         let kd_tree = KdTree::new(data);
 
         let eps = 1.2;
         let point = [0.0, 0.0, 0.0];
-        let neighbourhood = kd_tree.neighbourhood(&point, eps);
+        let neighbourhood = kd_tree.neighbourhood(&point, eps, Boundary::Closed);
         assert_eq!(neighbourhood.len(), 7);
         for pt in neighbourhood {
             assert!(distance(&point, pt) <= eps);
         }
     }
+
+    #[test]
+    fn nearest_query_test() {
+        let data = grid_points();
+
+        let kd_tree = KdTree::with_brute_force_size(data, 0);
+        assert!(KdTree::<f64, 3>::new(vec![]).nearest(&[0.0; 3]).is_none());
+
+        for point in [[0.1, 0.2, -0.1], [1.6, -0.7, 5.0], [-3.0, -3.0, -3.0]] {
+            let (dst, nearest) = kd_tree.nearest(&point).unwrap();
+            let knn = kd_tree.knn(&point, 1);
+            assert_eq!(dst, knn[0].0);
+            assert_eq!(nearest, knn[0].1);
+
+            let (dst, index) = kd_tree.nearest_by_index(&point).unwrap();
+            assert_eq!(dst, knn[0].0);
+            assert_eq!(&kd_tree.data()[index], knn[0].1);
+        }
+    }
+
+    #[test]
+    fn shell_query_test() {
+        let data = grid_points();
+
+        let kd_tree = KdTree::with_brute_force_size(data, 0);
+
+        let point = [0.0, 0.0, 0.0];
+        let shell = kd_tree.shell(&point, 1.0, 1.5, Boundary::Closed);
+        assert_eq!(shell.len(), 12);
+        for pt in shell {
+            let dst = distance(&point, pt);
+            assert!(1.0 < dst && dst <= 1.5);
+        }
+
+        for (r_min, r_max) in [(1.0, 1.5), (0.5, 2.0), (1.5, 10.0), (0.0, 0.0)] {
+            for point in [[0.0, 0.0, 0.0], [1.5, -0.5, 0.3], [-4.0, 2.0, 0.0]] {
+                let expected = kd_tree.count_neighbourhood(&point, r_max, Boundary::Closed)
+                    - kd_tree.count_neighbourhood(&point, r_min, Boundary::Closed);
+                assert_eq!(
+                    kd_tree.count_shell(&point, r_min, r_max, Boundary::Closed),
+                    expected
+                );
+                assert_eq!(
+                    kd_tree
+                        .shell_by_index(&point, r_min, r_max, Boundary::Closed)
+                        .len(),
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn first_within_query_test() {
+        let data = grid_points();
+
+        let kd_tree = KdTree::with_brute_force_size(data, 0);
+
+        for (point, eps) in [
+            ([0.5, 0.5, 0.5], 0.9),
+            ([3.0, 0.0, 0.0], 1.0),
+            ([-2.5, 2.5, 1.0], 0.5),
+        ] {
+            for boundary in [Boundary::Closed, Boundary::Open] {
+                let expected = kd_tree.count_neighbourhood(&point, eps, boundary) > 0;
+                assert_eq!(kd_tree.any_within(&point, eps, boundary), expected);
+
+                let first = kd_tree.first_within(&point, eps, boundary);
+                assert_eq!(first.is_some(), expected);
+                if let Some(index) = first {
+                    assert!(boundary.contains(distance(&point, &kd_tree.data()[index]), eps));
+                }
+            }
+        }
+        assert!(kd_tree.any_within(&[3.0, 0.0, 0.0], 1.0, Boundary::Closed));
+        assert!(!kd_tree.any_within(&[3.0, 0.0, 0.0], 1.0, Boundary::Open));
+    }
+
+    #[test]
+    fn furthest_query_test() {
+        let data = grid_points();
+
+        let kd_tree = KdTree::with_brute_force_size(data.clone(), 0);
+        assert!(KdTree::<f64, 3>::new(vec![]).furthest(&[0.0; 3]).is_none());
+
+        for point in [[0.1, 0.2, -0.1], [1.6, -0.7, 5.0], [-3.0, -3.0, -3.0]] {
+            let mut expected: Vec<_> = data.iter().map(|pt| distance(&point, pt)).collect();
+            expected.sort_by(|lhs, rhs| rhs.partial_cmp(lhs).unwrap());
+
+            let (dst, furthest) = kd_tree.furthest(&point).unwrap();
+            assert_eq!(dst, expected[0]);
+            assert_eq!(distance(&point, furthest), expected[0]);
+
+            for k in [1, 5, 30, 200] {
+                let k_furthest = kd_tree.k_furthest(&point, k);
+                let dsts: Vec<_> = k_furthest.iter().map(|(dst, _)| *dst).collect();
+                assert_eq!(dsts, expected[..k.min(expected.len())]);
+
+                let k_furthest = kd_tree.k_furthest_by_index(&point, k);
+                let dsts: Vec<_> = k_furthest.iter().map(|(dst, _)| *dst).collect();
+                assert_eq!(dsts, expected[..k.min(expected.len())]);
+            }
+        }
+    }
+
+    #[test]
+    fn reverse_knn_query_test() {
+        let data = grid_points();
+
+        let kd_tree = KdTree::with_brute_force_size(data, 0);
+
+        for k in [1, 4, 10] {
+            let radii = kd_tree.knn_radii(k);
+            assert_eq!(radii.k(), k);
+
+            for point in [[0.1, 0.2, -0.1], [1.6, -0.7, 2.5], [-3.0, -3.0, -3.0]] {
+                let expected: Vec<_> = (0..kd_tree.len())
+                    .filter(|i| {
+                        let pt = &kd_tree.data()[*i];
+                        let mut dsts: Vec<_> = kd_tree
+                            .data()
+                            .iter()
+                            .enumerate()
+                            .filter(|(j, _)| i != j)
+                            .map(|(_, other)| distance(pt, other))
+                            .collect();
+                        dsts.sort_by(|lhs, rhs| lhs.partial_cmp(rhs).unwrap());
+                        distance(pt, &point) <= dsts[k - 1]
+                    })
+                    .collect();
+
+                let mut reverse_knn = kd_tree.reverse_knn_by_index_with_radii(&point, &radii);
+                reverse_knn.sort();
+                assert_eq!(reverse_knn, expected);
+                let mut reverse_knn = kd_tree.reverse_knn_by_index(&point, k);
+                reverse_knn.sort();
+                assert_eq!(reverse_knn, expected);
+                assert_eq!(kd_tree.reverse_knn(&point, k).len(), expected.len());
+                assert_eq!(
+                    kd_tree.reverse_knn_with_radii(&point, &radii),
+                    kd_tree.reverse_knn(&point, k)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn split_axis_test() {
+        let mut data = vec![];
+        for x in 0..40 {
+            for y in 0..3 {
+                for z in 0..2 {
+                    data.push([x as f64 * 10.0, y as f64, z as f64 * 0.1]);
+                }
+            }
+        }
+
+        for split_axis in [
+            SplitAxis::RoundRobin,
+            SplitAxis::MaxSpread,
+            SplitAxis::MaxVariance,
+        ] {
+            let mut kd_tree = KdTree::with_split_axis(data.clone(), split_axis);
+            kd_tree.brute_force_size = 0;
+
+            for point in [[0.0, 0.0, 0.0], [123.0, 1.4, 0.05], [401.0, -1.0, 0.3]] {
+                let mut expected: Vec<_> = data.iter().map(|pt| distance(pt, &point)).collect();
+                expected.sort_by(|lhs, rhs| lhs.partial_cmp(rhs).unwrap());
+
+                let (dst, _) = kd_tree.nearest_by_index(&point).unwrap();
+                assert_eq!(dst, expected[0]);
+
+                let knn: Vec<_> = kd_tree
+                    .knn_by_index(&point, 7)
+                    .iter()
+                    .map(|(dst, _)| *dst)
+                    .collect();
+                assert_eq!(knn, expected[..7]);
+
+                let eps = 15.0;
+                let count = expected.iter().filter(|dst| **dst <= eps).count();
+                assert_eq!(
+                    kd_tree.count_neighbourhood(&point, eps, Boundary::Closed),
+                    count
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn stats_and_validate_test() {
+        use crate::ValidationError;
+
+        let data = grid_points();
+
+        let mut kd_tree = KdTree::with_brute_force_size(data.clone(), 4);
+        let stats = kd_tree.stats();
+        let leaf_points: usize = stats
+            .leaf_sizes
+            .iter()
+            .enumerate()
+            .map(|(n, c)| n * c)
+            .sum();
+        assert_eq!(stats.leaf_count, stats.leaf_sizes.iter().sum::<usize>());
+        assert!(stats.leaf_sizes.len() <= 5);
+        assert!(leaf_points > 0 && leaf_points < 125);
+        assert_eq!(stats.depth, 6);
+        assert_eq!(stats.level_volumes.len(), stats.depth);
+        assert_eq!(stats.level_volumes[0], 64.0);
+        assert_eq!(kd_tree.validate(), Ok(()));
+
+        // Move the largest point into the leftmost leaf.
+        let position = kd_tree
+            .data
+            .iter()
+            .position(|pt| pt == &[2.0, 2.0, 2.0])
+            .unwrap();
+        kd_tree.data.swap(0, position);
+        assert!(matches!(
+            kd_tree.validate(),
+            Err(ValidationError::Partition { .. })
+        ));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn tune_brute_force_size_test() {
+        use crate::QueryKind;
+
+        let mut data = vec![];
+        for x in 0..30 {
+            for y in 0..30 {
+                data.push([x as f64, y as f64 * 0.5]);
+            }
+        }
+        let queries = [[3.2, 4.1], [-1.0, 20.0], [15.5, 7.3]];
+
+        let mut kd_tree = KdTree::new(data.clone());
+        let brute_force_size = kd_tree.tune_brute_force_size(&queries, QueryKind::Knn(5));
+        assert_eq!(kd_tree.brute_force_size, brute_force_size);
+
+        for point in queries {
+            let expected = data
+                .iter()
+                .map(|pt| distance(pt, &point))
+                .fold(f64::INFINITY, f64::min);
+            assert_eq!(kd_tree.nearest(&point).unwrap().0, expected);
+        }
+    }
+
+    #[cfg(feature = "query-stats")]
+    #[test]
+    fn query_stats_test() {
+        use crate::take_query_stats;
+
+        let mut data = vec![];
+        for x in 0..50 {
+            for y in 0..50 {
+                data.push([x as f64, y as f64]);
+            }
+        }
+        let kd_tree = KdTree::with_brute_force_size(data, 4);
+
+        take_query_stats();
+        assert_eq!(
+            kd_tree.count_neighbourhood(&[10.0, 10.0], 1.5, Boundary::Closed),
+            9
+        );
+        let stats = take_query_stats();
+        assert!(stats.nodes_visited > 0 && stats.nodes_visited < 2500);
+        assert!(stats.subtrees_pruned > 0);
+        assert!(stats.distance_evaluations >= 9 && stats.distance_evaluations < 2500);
+        assert!(stats.leaf_scans > 0);
+        assert!(stats.max_depth > 1 && stats.max_depth <= 12);
+
+        assert_eq!(take_query_stats(), Default::default());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_test() {
+        let data = scattered_points(200);
+
+        for kd_tree in [
+            KdTree::with_brute_force_size(data.clone(), 3),
+            crate::KdTreeBuilder::new()
+                .split_strategy(crate::SplitStrategy::SlidingMidpoint)
+                .leaf_size(4)
+                .build(data.clone()),
+        ] {
+            let json = serde_json::to_string(&kd_tree).unwrap();
+            let restored: KdTree<f64, 2> = serde_json::from_str(&json).unwrap();
+
+            assert_eq!(restored.data(), kd_tree.data());
+            assert_eq!(restored.brute_force_size, kd_tree.brute_force_size);
+            assert_eq!(restored.validate(), Ok(()));
+            assert_eq!(
+                restored.knn_by_index(&[40.0, 20.0], 5),
+                kd_tree.knn_by_index(&[40.0, 20.0], 5)
+            );
+        }
+
+        // The axis policy is restored for rebuilding on extend.
+        let builder = crate::KdTreeBuilder::new().split_axis(SplitAxis::MaxVariance);
+        let json = serde_json::to_value(builder.build(data.clone())).unwrap();
+        let restored: KdTree<f64, 2> = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(restored.builder, builder);
+        let mut legacy = json;
+        legacy.as_object_mut().unwrap().remove("split_axis");
+        let restored: KdTree<f64, 2> = serde_json::from_value(legacy).unwrap();
+        assert_eq!(restored.builder.split_axis, SplitAxis::MaxSpread);
+
+        // Split information that is out of range is rejected instead of panicking later.
+        let kd_tree = crate::KdTreeBuilder::new()
+            .split_strategy(crate::SplitStrategy::SlidingMidpoint)
+            .build(data);
+        let json = serde_json::to_value(&kd_tree).unwrap();
+        let tampered: [fn(&mut serde_json::Value); 3] = [
+            |json| json["layout"]["Explicit"]["axes"] = vec![2; 200].into(),
+            |json| json["layout"]["Explicit"]["splits"][0]["split_index"] = 200.into(),
+            |json| {
+                json["layout"]["Explicit"]["splits"] =
+                    vec![json["layout"]["Explicit"]["splits"][0].clone()].into()
+            },
+        ];
+        for tamper in tampered {
+            let mut json = json.clone();
+            tamper(&mut json);
+            assert!(serde_json::from_value::<KdTree<f64, 2>>(json).is_err());
+        }
+        assert!(serde_json::from_value::<KdTree<f64, 2>>(json).is_ok());
+    }
+
+    #[cfg(feature = "half")]
+    #[test]
+    fn half_test() {
+        use half::{bf16, f16};
+
+        let data: Vec<[f32; 3]> = (0..300)
+            .map(|i| {
+                [
+                    (i * 37 % 101) as f32,
+                    (i * 53 % 97) as f32 * 0.5,
+                    (i % 7) as f32,
+                ]
+            })
+            .collect();
+        let kd_tree = KdTree::new(data.clone());
+        let f16_tree = KdTree::new(data.iter().map(|pt| pt.map(f16::from_f32)).collect());
+        let bf16_tree = KdTree::new(data.iter().map(|pt| pt.map(bf16::from_f32)).collect());
+        assert_eq!(f16_tree.validate(), Ok(()));
+
+        // All coordinates are exactly representable in f16, distances are f32.
+        for point in [[0.0, 0.0, 0.0], [50.0, 20.0, 3.0]] {
+            let expected = kd_tree.knn(&point, 8);
+            let knn = f16_tree.knn(&point.map(f16::from_f32), 8);
+            for ((dst, pt), (expected_dst, expected_pt)) in knn.iter().zip(&expected) {
+                assert_eq!(dst, expected_dst);
+                assert_eq!(pt.map(f16::to_f32), **expected_pt);
+            }
+            let point = point.map(bf16::from_f32);
+            let expected = bf16_tree
+                .data()
+                .iter()
+                .filter(|pt| distance(&point, pt) <= 10.0)
+                .count();
+            assert_eq!(
+                bf16_tree.count_neighbourhood(&point, 10.0f32, Boundary::Closed),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn format_test() {
+        use super::KdTreeView;
+        use crate::{FormatError, KdTreeBuilder, SplitStrategy};
+
+        let data: Vec<_> = (0..200)
+            .map(|i| {
+                [
+                    (i * 37 % 101) as f64,
+                    (i * 53 % 97) as f64 * 0.5,
+                    (i % 7) as f64,
+                ]
+            })
+            .collect();
+
+        for kd_tree in [
+            KdTree::with_brute_force_size(data.clone(), 3),
+            KdTree::with_split_axis(data.clone(), SplitAxis::MaxSpread),
+            KdTreeBuilder::new()
+                .split_strategy(SplitStrategy::SlidingMidpoint)
+                .leaf_size(4)
+                .brute_force_size(0)
+                .build(data.clone()),
+            KdTreeBuilder::new()
+                .split_strategy(SplitStrategy::BucketLeaf)
+                .split_axis(SplitAxis::MaxVariance)
+                .leaf_size(3)
+                .brute_force_size(0)
+                .build(data.clone()),
+        ] {
+            let bytes = kd_tree.to_bytes();
+            // Copy the bytes to an 8 byte aligned position, like in a memory map.
+            let mut buffer = vec![0u8; bytes.len() + 9];
+            let offset = buffer.as_ptr().align_offset(8);
+            buffer[offset..offset + bytes.len()].copy_from_slice(&bytes);
+            let aligned = &buffer[offset..offset + bytes.len()];
+
+            let view = KdTreeView::<f64, 3>::from_bytes(aligned).unwrap();
+            assert_eq!(view.data(), kd_tree.data());
+            assert_eq!(
+                view.data().as_ptr() as usize,
+                aligned[64..].as_ptr() as usize
+            );
+            assert_eq!(view.brute_force_size, kd_tree.brute_force_size);
+            assert_eq!(view.validate(), Ok(()));
+            for point in [[40.0, 20.0, 3.0], [-5.0, 100.0, 0.0]] {
+                assert_eq!(view.knn(&point, 7), kd_tree.knn(&point, 7));
+                assert_eq!(
+                    view.neighbourhood_by_index(&point, 15.0, Boundary::Closed),
+                    kd_tree.neighbourhood_by_index(&point, 15.0, Boundary::Closed)
+                );
+                assert_eq!(kd_tree.view().nearest(&point), kd_tree.nearest(&point));
+            }
+
+            assert_eq!(
+                KdTreeView::<f64, 2>::from_bytes(aligned).err(),
+                Some(FormatError::Dimension(3))
+            );
+            assert_eq!(
+                KdTreeView::<f32, 3>::from_bytes(aligned).err(),
+                Some(FormatError::ScalarType(2))
+            );
+            assert_eq!(
+                KdTreeView::<f64, 3>::from_bytes(&aligned[..aligned.len() - 1]).err(),
+                Some(FormatError::Length)
+            );
+            buffer.copy_within(offset..offset + bytes.len(), offset + 1);
+            assert_eq!(
+                KdTreeView::<f64, 3>::from_bytes(&buffer[offset + 1..offset + 1 + bytes.len()])
+                    .err(),
+                Some(FormatError::Alignment)
+            );
+
+            // Out of range split axes are rejected, the round robin layout stores none.
+            let points_end = 64 + kd_tree.len() * size_of::<[f64; 3]>();
+            if bytes.len() > points_end {
+                buffer[offset..offset + bytes.len()].copy_from_slice(&bytes);
+                buffer[offset + points_end..offset + points_end + kd_tree.len()].fill(3);
+                assert_eq!(
+                    KdTreeView::<f64, 3>::from_bytes(&buffer[offset..offset + bytes.len()]).err(),
+                    Some(FormatError::Splits(0))
+                );
+            }
+        }
+        assert_eq!(
+            KdTreeView::<f64, 3>::from_bytes(&[0; 64]).err(),
+            Some(FormatError::Magic)
+        );
+
+        let kd_tree = KdTree::new(data);
+        let view = KdTreeView::from_partitioned(kd_tree.data(), 0);
+        assert_eq!(view.validate(), Ok(()));
+        assert_eq!(
+            view.k_furthest(&[1.0, 2.0, 3.0], 4),
+            kd_tree.k_furthest(&[1.0, 2.0, 3.0], 4)
+        );
+    }
+
+    #[test]
+    fn from_slice_mut_test() {
+        let data = scattered_points(300);
+        let kd_tree = KdTree::new(data.clone());
+
+        // The tree only occupies a part of a larger buffer.
+        let mut buffer = vec![[-1.0; 2]; 10];
+        buffer.extend_from_slice(&data);
+        let kd_tree_mut = KdTree::from_slice_mut(&mut buffer[10..]);
+        assert_eq!(kd_tree_mut.validate(), Ok(()));
+        for point in [[0.0, 0.0], [50.0, 20.0], [120.0, 60.0]] {
+            assert_eq!(kd_tree_mut.knn(&point, 6), kd_tree.knn(&point, 6));
+            assert_eq!(
+                kd_tree_mut.count_neighbourhood(&point, 10.0, Boundary::Open),
+                kd_tree.count_neighbourhood(&point, 10.0, Boundary::Open)
+            );
+        }
+        assert_eq!(&buffer[10..], kd_tree.data());
+        assert_eq!(buffer[..10], [[-1.0; 2]; 10]);
+
+        let kd_tree_mut = crate::KdTreeBuilder::new()
+            .split_axis(SplitAxis::MaxVariance)
+            .leaf_size(3)
+            .build_slice_mut(&mut buffer[10..]);
+        assert_eq!(kd_tree_mut.validate(), Ok(()));
+        assert_eq!(
+            kd_tree_mut.nearest(&[50.0, 20.0]),
+            kd_tree.nearest(&[50.0, 20.0])
+        );
+    }
+
+    #[test]
+    fn integer_coordinate_test() {
+        let data: Vec<[i32; 2]> = (0..300)
+            .map(|i| [i * 37 % 101 - 50, i * 53 % 97 - 48])
+            .collect();
+        let kd_tree = KdTree::new(data.clone());
+        assert_eq!(kd_tree.validate(), Ok(()));
+
+        for point in [[0, 0], [-50, 48], [1000, -1000]] {
+            let mut expected: Vec<_> = data.iter().map(|pt| distance(&point, pt)).collect();
+            expected.sort();
+
+            // Distances are exact squared distances in i64.
+            let knn: Vec<_> = kd_tree
+                .knn(&point, 10)
+                .into_iter()
+                .map(|(dst, _)| dst)
+                .collect();
+            assert_eq!(knn, expected[..10]);
+            for squared_radius in [0, 25, 100, 401] {
+                let count = expected
+                    .iter()
+                    .filter(|dst| **dst <= squared_radius)
+                    .count();
+                assert_eq!(
+                    kd_tree.count_neighbourhood(&point, squared_radius, Boundary::Closed),
+                    count
+                );
+                assert_eq!(
+                    kd_tree
+                        .neighbourhood(&point, squared_radius, Boundary::Closed)
+                        .len(),
+                    count
+                );
+            }
+        }
+
+        let kd_tree = KdTree::new(vec![[u16::MAX, 0], [0, u16::MAX], [1, 1]]);
+        assert_eq!(kd_tree.nearest(&[0, 0]), Some((2u64, &[1, 1])));
+        assert_eq!(
+            kd_tree.furthest(&[u16::MAX, u16::MAX]),
+            Some((2 * (u16::MAX as u64 - 1).pow(2), &[1, 1]))
+        );
+    }
+
+    #[test]
+    fn leaf_distances_test() {
+        use crate::Coordinate;
+        use num_traits::Float;
+
+        fn check<T: Float + Coordinate<Distance = T>, const N: usize>() {
+            let mut state = 12345u64;
+            let mut next = move || {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+                T::from((state >> 11) as f64 / (1u64 << 53) as f64 * 20.0 - 10.0).unwrap()
+            };
+            let data: Vec<[T; N]> = (0..1000)
+                .map(|_| core::array::from_fn(|_| next()))
+                .collect();
+            let kd_tree = KdTree::new(data.clone());
+
+            for _ in 0..20 {
+                let point: [T; N] = core::array::from_fn(|_| next());
+                let expected: Vec<T> = data.iter().map(|pt| distance(&point, pt)).collect();
+                let mut leaf_distances = vec![];
+                T::leaf_distances(
+                    &point,
+                    data.len(),
+                    |i| data[i],
+                    |i, dst| leaf_distances.push((i, dst)),
+                );
+                assert!(leaf_distances
+                    .iter()
+                    .enumerate()
+                    .all(|(i, (position, dst))| i == *position && *dst == expected[i]));
+                let mut slice_leaf_distances = vec![];
+                T::slice_leaf_distances(&point, &data.concat(), |i, dst| {
+                    slice_leaf_distances.push((i, dst))
+                });
+                assert_eq!(slice_leaf_distances, leaf_distances);
+
+                let mut sorted = expected.clone();
+                sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                let knn: Vec<T> = kd_tree
+                    .knn(&point, 50)
+                    .into_iter()
+                    .map(|(dst, _)| dst)
+                    .collect();
+                assert_eq!(knn, sorted[..50]);
+                let radius = sorted[49];
+                assert_eq!(
+                    kd_tree.count_neighbourhood(&point, radius, Boundary::Closed),
+                    50
+                );
+                assert_eq!(
+                    kd_tree.neighbourhood(&point, radius, Boundary::Open).len(),
+                    49
+                );
+                assert_eq!(kd_tree.nearest(&point).unwrap().0, sorted[0]);
+                assert_eq!(kd_tree.furthest(&point).unwrap().0, sorted[data.len() - 1]);
+            }
+        }
+
+        check::<f32, 2>();
+        check::<f32, 3>();
+        check::<f32, 4>();
+        check::<f32, 8>();
+        check::<f64, 2>();
+        check::<f64, 3>();
+        check::<f64, 4>();
+        check::<f64, 8>();
+    }
+
+    #[test]
+    fn from_iter_extend_test() {
+        let data = scattered_points(200);
+
+        // Known and unknown length.
+        let kd_tree: KdTree<_, 2> = data.iter().copied().collect();
+        assert_eq!(kd_tree.data(), KdTree::new(data.clone()).data());
+        let kd_tree = KdTree::from_iter(data.iter().copied().filter(|pt| pt[0] < 50.0));
+        assert_eq!(kd_tree.validate(), Ok(()));
+        assert_eq!(kd_tree.len(), data.iter().filter(|pt| pt[0] < 50.0).count());
+
+        let mut kd_tree = KdTree::with_brute_force_size(data[..50].to_vec(), 3);
+        kd_tree.extend(data[50..].iter().copied());
+        assert_eq!(kd_tree.brute_force_size, 3);
+        assert_eq!(kd_tree.validate(), Ok(()));
+        assert_eq!(kd_tree.len(), data.len());
+        assert_eq!(kd_tree.nearest(&data[150]), Some((0.0, &data[150])));
+
+        // Trees of coordinates other than floats are rebuilt like KdTree::new.
+        let grid: Vec<[i32; 2]> = (0..100).map(|i| [i % 10, i / 10]).collect();
+        let mut kd_tree: KdTree<i32, 2> = grid[..40].iter().copied().collect();
+        kd_tree.extend(grid[40..].iter().copied());
+        assert_eq!(kd_tree.validate(), Ok(()));
+        assert_eq!(kd_tree.nearest(&[9, 9]), Some((0, &[9, 9])));
+
+        // The configuration of a builder is kept.
+        let builder = KdTreeBuilder::new()
+            .split_strategy(crate::SplitStrategy::SlidingMidpoint)
+            .split_axis(SplitAxis::MaxVariance)
+            .leaf_size(4)
+            .bounding_boxes(true)
+            .top_levels(3);
+        let mut kd_tree = builder.build(data[..50].to_vec());
+        let mut extended = kd_tree.data().to_vec();
+        extended.extend_from_slice(&data[50..]);
+        kd_tree.extend(data[50..].iter().copied());
+        let expected = builder.build(extended);
+        assert_eq!(kd_tree.validate(), Ok(()));
+        assert_eq!(kd_tree.data(), expected.data());
+        assert_eq!(kd_tree.leaf_size, 4);
+        match (&kd_tree.layout, &expected.layout) {
+            (
+                SplitLayout::Explicit { splits, .. },
+                SplitLayout::Explicit {
+                    splits: expected_splits,
+                    ..
+                },
+            ) => assert_eq!(splits, expected_splits),
+            _ => panic!("sliding midpoint splits are not kept"),
+        }
+        assert_eq!(
+            kd_tree.knn(&[40.0, 20.0], 5),
+            expected.knn(&[40.0, 20.0], 5)
+        );
+    }
 }
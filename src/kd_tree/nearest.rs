@@ -0,0 +1,113 @@
+use super::GenericKdTree;
+use crate::{distance, query_stats, top_levels::TopSplits, Coordinate, NearestParams};
+use num_traits::Zero;
+
+impl<T: Coordinate, const N: usize, D: AsRef<[[T; N]]>> GenericKdTree<'_, T, N, D> {
+    /// Returns the point closest to `point` together with its distance, or `None`
+    /// if the KdTree is empty. Equivalent to [Self::knn] with `k = 1`, but without
+    /// any allocation.
+    pub fn nearest<'a>(&'a self, point: &[T; N]) -> Option<(T::Distance, &'a [T; N])> {
+        let first = self.data().first()?;
+        let mut subtree_distance = [T::Distance::zero(); N];
+
+        let params = NearestParams {
+            point,
+            brute_force_size: self.leaf_scan_size(),
+        };
+        let best = (distance(first, point), first);
+        Some(Self::find_nearest_recursive(
+            self.data(),
+            &params,
+            &mut subtree_distance,
+            best,
+            self.top_splits(),
+        ))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[inline]
+    fn dispatch_find_nearest_recursive_on_subtrees<'a>(
+        subtree1: &'a [[T; N]],
+        subtree2: &'a [[T; N]],
+        split_point: &[T; N],
+        params: &NearestParams<T, N>,
+        subtree_distance: &mut [T::Distance; N],
+        best: (T::Distance, &'a [T; N]),
+        splits1: TopSplits<T, N>,
+        splits2: TopSplits<T, N>,
+        row: usize,
+    ) -> (T::Distance, &'a [T; N]) {
+        let mut best =
+            Self::find_nearest_recursive(subtree1, params, subtree_distance, best, splits1);
+
+        let row_value = subtree_distance[row];
+        subtree_distance[row] = params.point[row].offset(split_point[row]);
+        if T::norm(subtree_distance) < best.0 {
+            best = Self::find_nearest_recursive(subtree2, params, subtree_distance, best, splits2);
+        } else {
+            query_stats::subtree_pruned();
+        }
+        subtree_distance[row] = row_value;
+        best
+    }
+
+    fn find_nearest_recursive<'a>(
+        subtree: &'a [[T; N]],
+        params: &NearestParams<T, N>,
+        subtree_distance: &mut [T::Distance; N],
+        mut best: (T::Distance, &'a [T; N]),
+        splits: TopSplits<T, N>,
+    ) -> (T::Distance, &'a [T; N]) {
+        let _node = query_stats::enter_node();
+        if subtree.len() <= params.brute_force_size.max(1) {
+            query_stats::leaf_scan();
+            T::leaf_distances(
+                params.point,
+                subtree.len(),
+                |position| subtree[position],
+                |position, dst| {
+                    if dst < best.0 {
+                        best = (dst, &subtree[position]);
+                    }
+                },
+            );
+        } else {
+            let (split_index, row, splits1, splits2) = splits.split(subtree.len());
+            let split_point = splits.split_point(subtree, split_index);
+
+            let dst = distance(params.point, split_point);
+            if dst < best.0 {
+                best = (dst, &subtree[split_index]);
+            }
+
+            let subtree1 = &subtree[..split_index];
+            let subtree2 = &subtree[(split_index + 1)..];
+            if params.point[row] <= split_point[row] {
+                best = Self::dispatch_find_nearest_recursive_on_subtrees(
+                    subtree1,
+                    subtree2,
+                    split_point,
+                    params,
+                    subtree_distance,
+                    best,
+                    splits1,
+                    splits2,
+                    row,
+                );
+            } else if params.point[row] > split_point[row] {
+                best = Self::dispatch_find_nearest_recursive_on_subtrees(
+                    subtree2,
+                    subtree1,
+                    split_point,
+                    params,
+                    subtree_distance,
+                    best,
+                    splits2,
+                    splits1,
+                    row,
+                );
+            }
+        }
+        best
+    }
+}
@@ -0,0 +1,134 @@
+use super::GenericKdTree;
+use crate::{distance, query_stats, top_levels::TopSplits, Coordinate, NearestParams};
+use num_traits::Zero;
+
+impl<T: Coordinate, const N: usize, D: AsRef<[[T; N]]>> GenericKdTree<'_, T, N, D> {
+    /// Returns the index of the point closest to `point` together with its distance,
+    /// or `None` if the KdTree is empty. The index can be used together with
+    /// [Self::data] to retrieve the point.
+    pub fn nearest_by_index(&self, point: &[T; N]) -> Option<(T::Distance, usize)> {
+        let first = self.data().first()?;
+        let mut subtree_distance = [T::Distance::zero(); N];
+
+        let params = NearestParams {
+            point,
+            brute_force_size: self.leaf_scan_size(),
+        };
+        let best = (distance(first, point), 0);
+        Some(Self::find_nearest_by_index_recursive(
+            0,
+            self.data(),
+            &params,
+            &mut subtree_distance,
+            best,
+            self.top_splits(),
+        ))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[inline]
+    fn dispatch_find_nearest_by_index_recursive_on_subtrees(
+        subtree1_offset: usize,
+        subtree1: &[[T; N]],
+        subtree2_offset: usize,
+        subtree2: &[[T; N]],
+        split_point: &[T; N],
+        params: &NearestParams<T, N>,
+        subtree_distance: &mut [T::Distance; N],
+        best: (T::Distance, usize),
+        splits1: TopSplits<T, N>,
+        splits2: TopSplits<T, N>,
+        row: usize,
+    ) -> (T::Distance, usize) {
+        let mut best = Self::find_nearest_by_index_recursive(
+            subtree1_offset,
+            subtree1,
+            params,
+            subtree_distance,
+            best,
+            splits1,
+        );
+
+        let row_value = subtree_distance[row];
+        subtree_distance[row] = params.point[row].offset(split_point[row]);
+        if T::norm(subtree_distance) < best.0 {
+            best = Self::find_nearest_by_index_recursive(
+                subtree2_offset,
+                subtree2,
+                params,
+                subtree_distance,
+                best,
+                splits2,
+            );
+        } else {
+            query_stats::subtree_pruned();
+        }
+        subtree_distance[row] = row_value;
+        best
+    }
+
+    fn find_nearest_by_index_recursive(
+        subtree_offset: usize,
+        subtree: &[[T; N]],
+        params: &NearestParams<T, N>,
+        subtree_distance: &mut [T::Distance; N],
+        mut best: (T::Distance, usize),
+        splits: TopSplits<T, N>,
+    ) -> (T::Distance, usize) {
+        let _node = query_stats::enter_node();
+        if subtree.len() <= params.brute_force_size.max(1) {
+            query_stats::leaf_scan();
+            T::leaf_distances(
+                params.point,
+                subtree.len(),
+                |position| subtree[position],
+                |position, dst| {
+                    if dst < best.0 {
+                        best = (dst, subtree_offset + position);
+                    }
+                },
+            );
+        } else {
+            let (split_index, row, splits1, splits2) = splits.split(subtree.len());
+            let split_point = splits.split_point(subtree, split_index);
+
+            let dst = distance(params.point, split_point);
+            if dst < best.0 {
+                best = (dst, subtree_offset + split_index);
+            }
+
+            let subtree1 = &subtree[..split_index];
+            let subtree2 = &subtree[(split_index + 1)..];
+            if params.point[row] <= split_point[row] {
+                best = Self::dispatch_find_nearest_by_index_recursive_on_subtrees(
+                    subtree_offset,
+                    subtree1,
+                    subtree_offset + split_index + 1,
+                    subtree2,
+                    split_point,
+                    params,
+                    subtree_distance,
+                    best,
+                    splits1,
+                    splits2,
+                    row,
+                );
+            } else if params.point[row] > split_point[row] {
+                best = Self::dispatch_find_nearest_by_index_recursive_on_subtrees(
+                    subtree_offset + split_index + 1,
+                    subtree2,
+                    subtree_offset,
+                    subtree1,
+                    split_point,
+                    params,
+                    subtree_distance,
+                    best,
+                    splits2,
+                    splits1,
+                    row,
+                );
+            }
+        }
+        best
+    }
+}
@@ -1,61 +1,81 @@
-use super::KdTree;
-use crate::{distance, norm, NeighbourhoodParams};
+use super::GenericKdTree;
+use crate::{
+    bounds::NodeBoxes, distance, min_box_distance, query_stats, top_levels::TopSplits, Boundary,
+    Coordinate, NeighbourhoodParams,
+};
 use alloc::{vec, vec::Vec};
-use num_traits::Float;
+use num_traits::Zero;
 
-impl<T: Float + Clone, const N: usize> KdTree<T, N> {
-    /// Returns a list of references to points with a distance less than or equals to
-    /// epsilon from p.
-    pub fn neighbourhood<'a>(&'a self, point: &[T; N], epsilon: T) -> Vec<&'a [T; N]> {
-        let mut subtree_distance = [T::zero(); N];
+impl<T: Coordinate, const N: usize, D: AsRef<[[T; N]]>> GenericKdTree<'_, T, N, D> {
+    /// Returns a list of references to points within the ball of radius epsilon around p.
+    /// `boundary` determines whether points at a distance of exactly epsilon are included.
+    pub fn neighbourhood<'a>(
+        &'a self,
+        point: &[T; N],
+        epsilon: T::Distance,
+        boundary: Boundary,
+    ) -> Vec<&'a [T; N]> {
+        let mut subtree_distance = [T::Distance::zero(); N];
         let mut result = vec![];
 
         let params = NeighbourhoodParams {
             point,
             epsilon,
-            brute_force_size: self.brute_force_size,
+            boundary,
+            brute_force_size: self.leaf_scan_size(),
         };
 
         Self::find_neighbourhood_recursive(
-            self.data.as_slice(),
+            self.data(),
             &params,
             &mut subtree_distance,
             &mut result,
-            0,
+            self.top_splits(),
+            self.node_boxes(),
         );
         result
     }
 
+    #[allow(clippy::too_many_arguments)]
     #[inline]
     fn dispatch_find_neighbourhood_recursive_on_subtrees<'a>(
         subtree_offset1: &'a [[T; N]],
         subtree_offset2: &'a [[T; N]],
         split_point: &[T; N],
         params: &NeighbourhoodParams<T, N>,
-        subtree_distance: &mut [T; N],
+        subtree_distance: &mut [T::Distance; N],
         result: &mut Vec<&'a [T; N]>,
+        splits1: TopSplits<T, N>,
+        splits2: TopSplits<T, N>,
+        boxes1: NodeBoxes<T, N>,
+        boxes2: NodeBoxes<T, N>,
         row: usize,
     ) {
-        let next_row = (row + 1) % N;
-
         Self::find_neighbourhood_recursive(
             subtree_offset1,
             params,
             subtree_distance,
             result,
-            next_row,
+            splits1,
+            boxes1,
         );
 
         let row_value = subtree_distance[row];
-        subtree_distance[row] = params.point[row] - split_point[row];
-        if norm(subtree_distance) <= params.epsilon {
+        subtree_distance[row] = params.point[row].offset(split_point[row]);
+        if params
+            .boundary
+            .contains(T::norm(subtree_distance), params.epsilon)
+        {
             Self::find_neighbourhood_recursive(
                 subtree_offset2,
                 params,
                 subtree_distance,
                 result,
-                next_row,
+                splits2,
+                boxes2,
             );
+        } else {
+            query_stats::subtree_pruned();
         }
         subtree_distance[row] = row_value;
     }
@@ -63,22 +83,43 @@ impl<T: Float + Clone, const N: usize> KdTree<T, N> {
     fn find_neighbourhood_recursive<'a>(
         subtree: &'a [[T; N]],
         params: &NeighbourhoodParams<T, N>,
-        subtree_distance: &mut [T; N],
+        subtree_distance: &mut [T::Distance; N],
         result: &mut Vec<&'a [T; N]>,
-        row: usize,
+        splits: TopSplits<T, N>,
+        boxes: NodeBoxes<T, N>,
     ) {
-        if subtree.len() <= params.brute_force_size.max(1) {
-            for pt in subtree.iter() {
-                if distance(params.point, pt) <= params.epsilon {
-                    result.push(pt);
-                }
+        let _node = query_stats::enter_node();
+        if let Some(bounds) = boxes.bounds(subtree.len()) {
+            if !params
+                .boundary
+                .contains(min_box_distance(params.point, bounds), params.epsilon)
+            {
+                query_stats::subtree_pruned();
+                return;
             }
+        }
+        if subtree.len() <= params.brute_force_size.max(1) {
+            query_stats::leaf_scan();
+            T::leaf_distances(
+                params.point,
+                subtree.len(),
+                |position| subtree[position],
+                |position, dst| {
+                    if params.boundary.contains(dst, params.epsilon) {
+                        result.push(&subtree[position]);
+                    }
+                },
+            );
         } else {
-            let split_index = subtree.len() / 2;
-            let split_point = &subtree[split_index];
+            let (split_index, row, splits1, splits2) = splits.split(subtree.len());
+            let (boxes1, boxes2) = boxes.split(subtree.len());
+            let split_point = splits.split_point(subtree, split_index);
 
-            if distance(split_point, params.point) <= params.epsilon {
-                result.push(split_point);
+            if params
+                .boundary
+                .contains(distance(split_point, params.point), params.epsilon)
+            {
+                result.push(&subtree[split_index]);
             }
 
             let subtree1 = &subtree[..split_index];
@@ -91,6 +132,10 @@ impl<T: Float + Clone, const N: usize> KdTree<T, N> {
                     params,
                     subtree_distance,
                     result,
+                    splits1,
+                    splits2,
+                    boxes1,
+                    boxes2,
                     row,
                 );
             } else if params.point[row] > split_point[row] {
@@ -101,6 +146,10 @@ impl<T: Float + Clone, const N: usize> KdTree<T, N> {
                     params,
                     subtree_distance,
                     result,
+                    splits2,
+                    splits1,
+                    boxes2,
+                    boxes1,
                     row,
                 );
             }
@@ -0,0 +1,46 @@
+use super::GenericKdTree;
+use crate::{Coordinate, KnnRadii};
+use alloc::vec::Vec;
+
+impl<T: Coordinate, const N: usize, D: AsRef<[[T; N]]>> GenericKdTree<'_, T, N, D> {
+    /// Computes the distance of every point to its k-th nearest neighbour. The result
+    /// can be passed to [Self::reverse_knn_with_radii] and
+    /// [Self::reverse_knn_by_index_with_radii] for any number of queries on this tree.
+    pub fn knn_radii(&self, k: usize) -> KnnRadii<T::Distance> {
+        let radii = self
+            .data()
+            .iter()
+            .map(|pt| match self.knn(pt, k + 1).get(k) {
+                Some((dst, _)) => *dst,
+                None => T::MAX_DISTANCE,
+            })
+            .collect();
+        KnnRadii::new::<N>(
+            self.id,
+            k,
+            radii,
+            |index| index,
+            self.splits(),
+            self.leaf_size,
+        )
+    }
+
+    /// Returns a list of references to all points that have `point` among their k
+    /// nearest neighbours. Computes the [KnnRadii] of all points first, use
+    /// [Self::reverse_knn_with_radii] to reuse them for several queries.
+    pub fn reverse_knn<'a>(&'a self, point: &[T; N], k: usize) -> Vec<&'a [T; N]> {
+        self.reverse_knn_with_radii(point, &self.knn_radii(k))
+    }
+
+    /// Like [Self::reverse_knn] with radii precomputed by [Self::knn_radii] of this tree.
+    pub fn reverse_knn_with_radii<'a>(
+        &'a self,
+        point: &[T; N],
+        radii: &KnnRadii<T::Distance>,
+    ) -> Vec<&'a [T; N]> {
+        self.reverse_knn_by_index_with_radii(point, radii)
+            .into_iter()
+            .map(|index| &self.data()[index])
+            .collect()
+    }
+}
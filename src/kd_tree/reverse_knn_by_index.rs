@@ -0,0 +1,158 @@
+use super::GenericKdTree;
+use crate::{distance, query_stats, top_levels::TopSplits, Coordinate, KnnRadii, ReverseKnnParams};
+use alloc::{vec, vec::Vec};
+use num_traits::Zero;
+
+impl<T: Coordinate, const N: usize, D: AsRef<[[T; N]]>> GenericKdTree<'_, T, N, D> {
+    /// Returns the index of all points that have `point` among their k nearest
+    /// neighbours. Computes the [KnnRadii] of all points first, use
+    /// [Self::reverse_knn_by_index_with_radii] to reuse them for several queries.
+    pub fn reverse_knn_by_index(&self, point: &[T; N], k: usize) -> Vec<usize> {
+        self.reverse_knn_by_index_with_radii(point, &self.knn_radii(k))
+    }
+
+    /// Like [Self::reverse_knn_by_index] with radii precomputed by [Self::knn_radii] of
+    /// this tree. Only subtrees containing a point whose knn ball can contain `point`
+    /// are visited. Panics if `radii` were computed by another tree.
+    pub fn reverse_knn_by_index_with_radii(
+        &self,
+        point: &[T; N],
+        radii: &KnnRadii<T::Distance>,
+    ) -> Vec<usize> {
+        radii.assert_tree(self.id);
+        let mut subtree_distance = [T::Distance::zero(); N];
+        let mut result = vec![];
+
+        let params = ReverseKnnParams {
+            point,
+            radii: radii.radii(),
+            brute_force_size: self.leaf_scan_size(),
+        };
+
+        Self::find_reverse_knn_by_index_recursive(
+            0,
+            self.data(),
+            radii.subtree_max(),
+            &params,
+            &mut subtree_distance,
+            &mut result,
+            self.top_splits(),
+        );
+        result
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[inline]
+    fn dispatch_find_reverse_knn_by_index_recursive_on_subtrees(
+        subtree1_offset: usize,
+        subtree1: &[[T; N]],
+        subtree1_max: &[T::Distance],
+        subtree2_offset: usize,
+        subtree2: &[[T; N]],
+        subtree2_max: &[T::Distance],
+        split_point: &[T; N],
+        params: &ReverseKnnParams<T, N>,
+        subtree_distance: &mut [T::Distance; N],
+        result: &mut Vec<usize>,
+        splits1: TopSplits<T, N>,
+        splits2: TopSplits<T, N>,
+        row: usize,
+    ) {
+        Self::find_reverse_knn_by_index_recursive(
+            subtree1_offset,
+            subtree1,
+            subtree1_max,
+            params,
+            subtree_distance,
+            result,
+            splits1,
+        );
+
+        let row_value = subtree_distance[row];
+        subtree_distance[row] = params.point[row].offset(split_point[row]);
+        Self::find_reverse_knn_by_index_recursive(
+            subtree2_offset,
+            subtree2,
+            subtree2_max,
+            params,
+            subtree_distance,
+            result,
+            splits2,
+        );
+        subtree_distance[row] = row_value;
+    }
+
+    fn find_reverse_knn_by_index_recursive(
+        subtree_offset: usize,
+        subtree: &[[T; N]],
+        subtree_max: &[T::Distance],
+        params: &ReverseKnnParams<T, N>,
+        subtree_distance: &mut [T::Distance; N],
+        result: &mut Vec<usize>,
+        splits: TopSplits<T, N>,
+    ) {
+        let _node = query_stats::enter_node();
+        if subtree.len() <= params.brute_force_size.max(1) {
+            query_stats::leaf_scan();
+            T::leaf_distances(
+                params.point,
+                subtree.len(),
+                |position| subtree[position],
+                |position, dst| {
+                    if dst <= params.radii[subtree_offset + position] {
+                        result.push(subtree_offset + position);
+                    }
+                },
+            );
+        } else {
+            let (split_index, row, splits1, splits2) = splits.split(subtree.len());
+            if T::norm(subtree_distance) > subtree_max[split_index] {
+                query_stats::subtree_pruned();
+                return;
+            }
+            let split_point = splits.split_point(subtree, split_index);
+
+            if distance(split_point, params.point) <= params.radii[subtree_offset + split_index] {
+                result.push(subtree_offset + split_index);
+            }
+
+            let subtree1 = &subtree[..split_index];
+            let subtree2 = &subtree[(split_index + 1)..];
+            let subtree1_max = &subtree_max[..split_index];
+            let subtree2_max = &subtree_max[(split_index + 1)..];
+            if params.point[row] <= split_point[row] {
+                Self::dispatch_find_reverse_knn_by_index_recursive_on_subtrees(
+                    subtree_offset,
+                    subtree1,
+                    subtree1_max,
+                    subtree_offset + split_index + 1,
+                    subtree2,
+                    subtree2_max,
+                    split_point,
+                    params,
+                    subtree_distance,
+                    result,
+                    splits1,
+                    splits2,
+                    row,
+                );
+            } else if params.point[row] > split_point[row] {
+                Self::dispatch_find_reverse_knn_by_index_recursive_on_subtrees(
+                    subtree_offset + split_index + 1,
+                    subtree2,
+                    subtree2_max,
+                    subtree_offset,
+                    subtree1,
+                    subtree1_max,
+                    split_point,
+                    params,
+                    subtree_distance,
+                    result,
+                    splits2,
+                    splits1,
+                    row,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use super::GenericKdTree;
+use crate::{
+    distance, max_box_distance, query_stats, top_levels::TopSplits, unbounded_box, Boundary,
+    Coordinate, ShellParams,
+};
+use alloc::{vec, vec::Vec};
+use num_traits::Zero;
+
+impl<T: Coordinate, const N: usize, D: AsRef<[[T; N]]>> GenericKdTree<'_, T, N, D> {
+    /// Returns the index of all points inside the ball of radius `r_max` but outside
+    /// the ball of radius `r_min` around p. See [Self::shell] for the meaning of
+    /// `boundary`.
+    pub fn shell_by_index(
+        &self,
+        point: &[T; N],
+        r_min: T::Distance,
+        r_max: T::Distance,
+        boundary: Boundary,
+    ) -> Vec<usize> {
+        let mut subtree_distance = [T::Distance::zero(); N];
+        let mut bounds = unbounded_box();
+        let mut result = vec![];
+
+        let params = ShellParams {
+            point,
+            r_min,
+            r_max,
+            boundary,
+            brute_force_size: self.leaf_scan_size(),
+        };
+
+        Self::find_shell_by_index_recursive(
+            0,
+            self.data(),
+            &params,
+            &mut subtree_distance,
+            &mut bounds,
+            &mut result,
+            self.top_splits(),
+        );
+        result
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[inline]
+    fn dispatch_find_shell_by_index_recursive_on_subtrees(
+        subtree1_offset: usize,
+        subtree1: &[[T; N]],
+        subtree2_offset: usize,
+        subtree2: &[[T; N]],
+        split_point: &[T; N],
+        params: &ShellParams<T, N>,
+        subtree_distance: &mut [T::Distance; N],
+        bounds: &mut [[T; N]; 2],
+        result: &mut Vec<usize>,
+        splits1: TopSplits<T, N>,
+        splits2: TopSplits<T, N>,
+        row: usize,
+    ) {
+        let (near_bound, far_bound) = if params.point[row] <= split_point[row] {
+            (1, 0)
+        } else {
+            (0, 1)
+        };
+
+        let bound_value = bounds[near_bound][row];
+        bounds[near_bound][row] = split_point[row];
+        Self::find_shell_by_index_recursive(
+            subtree1_offset,
+            subtree1,
+            params,
+            subtree_distance,
+            bounds,
+            result,
+            splits1,
+        );
+        bounds[near_bound][row] = bound_value;
+
+        let row_value = subtree_distance[row];
+        subtree_distance[row] = params.point[row].offset(split_point[row]);
+        if params
+            .boundary
+            .contains(T::norm(subtree_distance), params.r_max)
+        {
+            let bound_value = bounds[far_bound][row];
+            bounds[far_bound][row] = split_point[row];
+            Self::find_shell_by_index_recursive(
+                subtree2_offset,
+                subtree2,
+                params,
+                subtree_distance,
+                bounds,
+                result,
+                splits2,
+            );
+            bounds[far_bound][row] = bound_value;
+        } else {
+            query_stats::subtree_pruned();
+        }
+        subtree_distance[row] = row_value;
+    }
+
+    fn find_shell_by_index_recursive(
+        subtree_offset: usize,
+        subtree: &[[T; N]],
+        params: &ShellParams<T, N>,
+        subtree_distance: &mut [T::Distance; N],
+        bounds: &mut [[T; N]; 2],
+        result: &mut Vec<usize>,
+        splits: TopSplits<T, N>,
+    ) {
+        let _node = query_stats::enter_node();
+        if params
+            .boundary
+            .contains(max_box_distance(params.point, bounds), params.r_min)
+        {
+            query_stats::subtree_pruned();
+            return;
+        }
+
+        if subtree.len() <= params.brute_force_size.max(1) {
+            query_stats::leaf_scan();
+            T::leaf_distances(
+                params.point,
+                subtree.len(),
+                |position| subtree[position],
+                |position, dst| {
+                    if params.boundary.contains(dst, params.r_max)
+                        && !params.boundary.contains(dst, params.r_min)
+                    {
+                        result.push(subtree_offset + position);
+                    }
+                },
+            );
+        } else {
+            let (split_index, row, splits1, splits2) = splits.split(subtree.len());
+            let split_point = splits.split_point(subtree, split_index);
+
+            let dst = distance(split_point, params.point);
+            if params.boundary.contains(dst, params.r_max)
+                && !params.boundary.contains(dst, params.r_min)
+            {
+                result.push(subtree_offset + split_index);
+            }
+
+            let subtree1 = &subtree[..split_index];
+            let subtree2 = &subtree[(split_index + 1)..];
+            if params.point[row] <= split_point[row] {
+                Self::dispatch_find_shell_by_index_recursive_on_subtrees(
+                    subtree_offset,
+                    subtree1,
+                    subtree_offset + split_index + 1,
+                    subtree2,
+                    split_point,
+                    params,
+                    subtree_distance,
+                    bounds,
+                    result,
+                    splits1,
+                    splits2,
+                    row,
+                );
+            } else if params.point[row] > split_point[row] {
+                Self::dispatch_find_shell_by_index_recursive_on_subtrees(
+                    subtree_offset + split_index + 1,
+                    subtree2,
+                    subtree_offset,
+                    subtree1,
+                    split_point,
+                    params,
+                    subtree_distance,
+                    bounds,
+                    result,
+                    splits2,
+                    splits1,
+                    row,
+                );
+            }
+        }
+    }
+}
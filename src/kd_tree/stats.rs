@@ -0,0 +1,38 @@
+use super::GenericKdTree;
+use crate::{
+    stats::{collect_tree_stats, validate_partition},
+    Coordinate, TreeStats, ValidationError,
+};
+use num_traits::Float;
+
+impl<T: Coordinate, const N: usize, D: AsRef<[[T; N]]>> GenericKdTree<'_, T, N, D> {
+    /// Returns the depth, the leaf sizes and the bounding box volumes per level of the
+    /// KdTree. Leaves are the subtrees queries scan by brute force with the current
+    /// `brute_force_size`.
+    pub fn stats(&self) -> TreeStats<T>
+    where
+        T: Float,
+    {
+        collect_tree_stats(
+            self.len(),
+            &|position| self.data()[position],
+            self.splits(),
+            self.leaf_scan_size(),
+        )
+    }
+
+    /// Checks that the split point of every node partitions its subtree: all points
+    /// before it are less than or equal and all points after it are greater than or
+    /// equal on the split axis.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if !self.layout.has_len(self.len()) {
+            return Err(ValidationError::Layout { position: 0 });
+        }
+        validate_partition(
+            self.len(),
+            &|position| self.data()[position],
+            self.splits(),
+            self.leaf_size,
+        )
+    }
+}
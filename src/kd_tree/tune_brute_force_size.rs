@@ -0,0 +1,35 @@
+use super::GenericKdTree;
+use crate::{tune::fastest_brute_force_size, Coordinate, QueryKind};
+use core::hint::black_box;
+
+impl<T: Coordinate, const N: usize, D: AsRef<[[T; N]]>> GenericKdTree<'_, T, N, D> {
+    /// Times `sample_queries` of the given kind for a range of candidate values, sets
+    /// the fastest one as `brute_force_size` and returns it. The samples should be
+    /// representative for the actual workload.
+    pub fn tune_brute_force_size(
+        &mut self,
+        sample_queries: &[[T; N]],
+        query_kind: QueryKind<T::Distance>,
+    ) -> usize {
+        self.brute_force_size = fastest_brute_force_size(|brute_force_size| {
+            self.brute_force_size = brute_force_size;
+            for point in sample_queries {
+                match query_kind {
+                    QueryKind::Nearest => {
+                        black_box(self.nearest(point));
+                    }
+                    QueryKind::Knn(k) => {
+                        black_box(self.knn(point, k));
+                    }
+                    QueryKind::Neighbourhood(epsilon, boundary) => {
+                        black_box(self.neighbourhood(point, epsilon, boundary));
+                    }
+                    QueryKind::CountNeighbourhood(epsilon, boundary) => {
+                        black_box(self.count_neighbourhood(point, epsilon, boundary));
+                    }
+                }
+            }
+        });
+        self.brute_force_size
+    }
+}
@@ -0,0 +1,133 @@
+use alloc::{vec, vec::Vec};
+use core::sync::atomic::{AtomicUsize, Ordering};
+use num_traits::Zero;
+
+use crate::split::Splits;
+
+/// Distance of every point of a K-d Tree to its k-th nearest neighbour.
+///
+/// Created by [crate::KdTree::knn_radii] or [crate::KdIndexTree::knn_radii] and used by
+/// the reverse knn queries. The radii are only valid for the tree that created them, the
+/// queries panic if they are passed to any other tree, including a rebuilt one.
+#[derive(Debug, Clone)]
+pub struct KnnRadii<T> {
+    k: usize,
+
+    /// The tree the radii were computed for.
+    tree: TreeId,
+
+    /// Distance to the k-th nearest neighbour, in the order of the tree's data.
+    radii: Vec<T>,
+
+    /// Largest radius within the subtree whose split point is stored at the same
+    /// position of the tree. Not set for leaves.
+    subtree_max: Vec<T>,
+}
+
+impl<T: Copy + PartialOrd + Zero> KnnRadii<T> {
+    /// Creates the radii from the k-th neighbour distances given in data order.
+    /// `tree_order(i)` returns the data index of the point at position `i` of the tree,
+    /// whose nodes are split according to `splits` down to leaves of `leaf_size` points.
+    pub(crate) fn new<const N: usize>(
+        tree: TreeId,
+        k: usize,
+        radii: Vec<T>,
+        tree_order: impl Fn(usize) -> usize,
+        splits: Splits,
+        leaf_size: usize,
+    ) -> Self {
+        let mut subtree_max = vec![T::zero(); radii.len()];
+        Self::fill_subtree_max_recursive::<N>(
+            &mut subtree_max,
+            0,
+            &|i| radii[tree_order(i)],
+            splits,
+            leaf_size,
+        );
+        Self {
+            k,
+            tree,
+            radii,
+            subtree_max,
+        }
+    }
+
+    fn fill_subtree_max_recursive<const N: usize>(
+        subtree_max: &mut [T],
+        subtree_offset: usize,
+        radius: &impl Fn(usize) -> T,
+        splits: Splits,
+        leaf_size: usize,
+    ) -> T {
+        if subtree_max.len() <= leaf_size.max(1) {
+            return (0..subtree_max.len())
+                .map(|i| radius(subtree_offset + i))
+                .fold(T::zero(), max);
+        }
+
+        let (split_index, _, splits1, splits2) = splits.split::<N>(subtree_max.len());
+        let (subtree1, subtree2) = subtree_max.split_at_mut(split_index);
+        let (split, subtree2) = subtree2.split_first_mut().unwrap();
+
+        let max1 = Self::fill_subtree_max_recursive::<N>(
+            subtree1,
+            subtree_offset,
+            radius,
+            splits1,
+            leaf_size,
+        );
+        let max2 = Self::fill_subtree_max_recursive::<N>(
+            subtree2,
+            subtree_offset + split_index + 1,
+            radius,
+            splits2,
+            leaf_size,
+        );
+        *split = max(max(radius(subtree_offset + split_index), max1), max2);
+        *split
+    }
+
+    /// The number of neighbours the radii were computed for.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Distance of every point to its k-th nearest neighbour, not counting the point
+    /// itself, in the order of the tree's data. [crate::Coordinate::MAX_DISTANCE] if the
+    /// tree holds no more than k points.
+    pub fn radii(&self) -> &[T] {
+        &self.radii
+    }
+
+    pub(crate) fn subtree_max(&self) -> &[T] {
+        &self.subtree_max
+    }
+
+    /// Panics if the radii were not computed by the tree identified by `tree`.
+    pub(crate) fn assert_tree(&self, tree: TreeId) {
+        assert!(
+            self.tree == tree,
+            "KnnRadii were computed for a different tree."
+        );
+    }
+}
+
+/// Identifies a tree with the order and layout of its points, which are fixed after
+/// construction. Every constructed tree gets a new id, views share the id of their tree.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct TreeId(usize);
+
+impl TreeId {
+    pub(crate) fn new() -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        TreeId(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+fn max<T: PartialOrd>(lhs: T, rhs: T) -> T {
+    if rhs > lhs {
+        rhs
+    } else {
+        lhs
+    }
+}
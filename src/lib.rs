@@ -2,21 +2,85 @@
 #![no_std]
 
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
+mod bounds;
+mod builder;
+mod coordinate;
+pub mod dyn_kd_tree;
+#[cfg(feature = "std")]
+pub mod external_kd_tree;
+pub mod format;
+mod index;
 pub mod kd_index_tree;
 pub mod kd_tree;
+pub mod knn_radii;
+mod point_chunks;
+mod point_source;
+mod query_stats;
+#[cfg(feature = "serde")]
+mod serialization;
+#[cfg(feature = "simd")]
+mod simd;
+mod split;
+mod stats;
+mod top_levels;
+#[cfg(feature = "std")]
+mod tune;
 
 use core::marker::PhantomData;
 
-pub use kd_index_tree::KdIndexTree;
-pub use kd_tree::KdTree;
+pub use builder::{KdTreeBuilder, SplitStrategy};
+pub use coordinate::Coordinate;
+pub use dyn_kd_tree::DynKdTree;
+#[cfg(feature = "std")]
+pub use external_kd_tree::{ExternalKdTree, ExternalKdTreeBuilder};
+pub use format::{FormatError, FormatScalar};
+pub use index::{IndexRangeError, TreeIndex};
+pub use kd_index_tree::{KdIndexTree, OwnedKdIndexTree};
+pub use kd_tree::{GenericKdTree, KdTree, KdTreeMut, KdTreeView};
+pub use knn_radii::KnnRadii;
+pub use point_chunks::PointChunks;
+#[cfg(feature = "std")]
+pub use point_chunks::PointReader;
+pub use point_source::{OwnedPoints, PointSource, Projection};
+#[cfg(feature = "query-stats")]
+pub use query_stats::{take_query_stats, QueryStats};
+#[cfg(feature = "serde")]
+pub use serialization::SerializedKdIndexTree;
+pub use split::SplitAxis;
+pub use stats::{TreeStats, ValidationError};
+#[cfg(feature = "std")]
+pub use tune::QueryKind;
 
-use num_traits::Float;
+use num_traits::Zero;
+
+/// Determines whether points at exactly the query radius belong to the result
+/// of a radius query.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Boundary {
+    /// Closed ball: all points with a distance less than or equals to the radius.
+    Closed,
+    /// Open ball: all points with a distance strictly less than the radius.
+    Open,
+}
+
+impl Boundary {
+    #[inline]
+    fn contains<T: PartialOrd>(self, distance: T, radius: T) -> bool {
+        match self {
+            Boundary::Closed => distance <= radius,
+            Boundary::Open => distance < radius,
+        }
+    }
+}
 
 #[derive(Debug, Copy, Clone)]
-struct NeighbourhoodParams<'a, T, const N: usize> {
+struct NeighbourhoodParams<'a, T: Coordinate, const N: usize> {
     point: &'a [T; N],
-    epsilon: T,
+    epsilon: T::Distance,
+    boundary: Boundary,
     brute_force_size: usize,
 }
 
@@ -27,6 +91,43 @@ struct KnnParams<'a, T, const N: usize> {
     brute_force_size: usize,
 }
 
+#[derive(Debug, Copy, Clone)]
+struct NearestParams<'a, T, const N: usize> {
+    point: &'a [T; N],
+    brute_force_size: usize,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct DynNeighbourhoodParams<'a, T: Coordinate> {
+    point: &'a [T],
+    epsilon: T::Distance,
+    boundary: Boundary,
+    brute_force_size: usize,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct DynKnnParams<'a, T> {
+    point: &'a [T],
+    k: core::num::NonZero<usize>,
+    brute_force_size: usize,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct ShellParams<'a, T: Coordinate, const N: usize> {
+    point: &'a [T; N],
+    r_min: T::Distance,
+    r_max: T::Distance,
+    boundary: Boundary,
+    brute_force_size: usize,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct ReverseKnnParams<'a, T: Coordinate, const N: usize> {
+    point: &'a [T; N],
+    radii: &'a [T::Distance],
+    brute_force_size: usize,
+}
+
 #[derive(Copy, Clone)]
 struct FilteredKnnParams<'a, T, const N: usize, P, F: Fn(P) -> bool> {
     point: &'a [T; N],
@@ -36,21 +137,80 @@ struct FilteredKnnParams<'a, T, const N: usize, P, F: Fn(P) -> bool> {
     _p: PhantomData<P>,
 }
 
+#[allow(clippy::needless_range_loop)]
 #[inline]
-fn distance<T: Float, const N: usize>(v1: &[T; N], v2: &[T; N]) -> T {
-    let mut dst = T::zero();
+fn distance<T: Coordinate, const N: usize>(v1: &[T; N], v2: &[T; N]) -> T::Distance {
+    query_stats::distance_evaluation();
+    let mut sum = T::Distance::zero();
     for i in 0..N {
-        dst = dst + (v1[i] - v2[i]).powi(2);
+        sum = T::add_square(sum, v1[i].offset(v2[i]));
     }
-    dst.sqrt()
+    T::from_sum_of_squares(sum)
 }
 
-#[allow(clippy::needless_range_loop)]
+/// [distance] of points whose dimension is only known at runtime.
 #[inline]
-fn norm<T: Float, const N: usize>(v: &[T; N]) -> T {
-    let mut norm = T::zero();
-    for i in 0..N {
-        norm = norm + v[i].powi(2);
+fn slice_distance<T: Coordinate>(v1: &[T], v2: &[T]) -> T::Distance {
+    query_stats::distance_evaluation();
+    let mut sum = T::Distance::zero();
+    for (x1, x2) in v1.iter().zip(v2) {
+        sum = T::add_square(sum, x1.offset(*x2));
     }
-    norm.sqrt()
+    T::from_sum_of_squares(sum)
+}
+
+/// Upper bound of the memory preallocated from an untrusted size hint.
+const MAX_PREALLOC_BYTES: usize = 1024 * 1024;
+
+/// Number of elements of type `E` to preallocate for a size hint of `hint` elements.
+fn prealloc_capacity<E>(hint: usize) -> usize {
+    hint.min(MAX_PREALLOC_BYTES / core::mem::size_of::<E>().max(1))
+}
+
+/// Smallest distance between `point` and the axis aligned box `[lower, upper]`.
+#[inline]
+fn min_box_distance<T: Coordinate, const N: usize>(
+    point: &[T; N],
+    bounds: &[[T; N]; 2],
+) -> T::Distance {
+    T::norm(&core::array::from_fn::<_, N, _>(|i| {
+        if point[i] < bounds[0][i] {
+            bounds[0][i].offset(point[i])
+        } else if point[i] > bounds[1][i] {
+            point[i].offset(bounds[1][i])
+        } else {
+            T::Distance::zero()
+        }
+    }))
+}
+
+/// Largest distance between `point` and the axis aligned box `[lower, upper]`.
+#[inline]
+fn max_box_distance<T: Coordinate, const N: usize>(
+    point: &[T; N],
+    bounds: &[[T; N]; 2],
+) -> T::Distance {
+    T::norm(&core::array::from_fn::<_, N, _>(|i| {
+        let lower = point[i].offset(bounds[0][i]);
+        let upper = bounds[1][i].offset(point[i]);
+        if upper > lower {
+            upper
+        } else {
+            lower
+        }
+    }))
+}
+
+/// Bounds of the whole space. Narrowed to the cell of a subtree while descending.
+#[inline]
+fn unbounded_box<T: Coordinate, const N: usize>() -> [[T; N]; 2] {
+    [[T::MIN; N], [T::MAX; N]]
+}
+
+/// Irregularly scattered 2D points shared by the tests.
+#[cfg(test)]
+fn scattered_points(n: usize) -> alloc::vec::Vec<[f64; 2]> {
+    (0..n)
+        .map(|i| [(i * 37 % 101) as f64, (i * 53 % 97) as f64 * 0.5])
+        .collect()
 }
@@ -0,0 +1,153 @@
+use alloc::vec::Vec;
+
+/// A source that yields points chunk by chunk, e.g. the parser of a point cloud file.
+/// [crate::KdTreeBuilder::build_from_chunks] appends the chunks directly to the points of
+/// the tree, so no intermediate buffer is kept around.
+///
+/// ```
+/// use neighbourhood::{KdTreeBuilder, PointChunks};
+///
+/// struct Grid {
+///     row: usize,
+/// }
+///
+/// impl PointChunks<f64, 2> for Grid {
+///     type Error = core::convert::Infallible;
+///
+///     fn read_chunk(&mut self, points: &mut Vec<[f64; 2]>) -> Result<usize, Self::Error> {
+///         if self.row == 10 {
+///             return Ok(0);
+///         }
+///         points.extend((0..10).map(|column| [self.row as f64, column as f64]));
+///         self.row += 1;
+///         Ok(10)
+///     }
+/// }
+///
+/// let kd_tree = KdTreeBuilder::new().build_from_chunks(Grid { row: 0 }).unwrap();
+/// assert_eq!(kd_tree.len(), 100);
+/// ```
+pub trait PointChunks<T, const N: usize> {
+    /// Error of reading a chunk.
+    type Error;
+
+    /// Appends the next chunk of points to `points` and returns the number of appended
+    /// points. Returns 0 once the source is exhausted.
+    fn read_chunk(&mut self, points: &mut Vec<[T; N]>) -> Result<usize, Self::Error>;
+
+    /// Number of points that remain, if known. Used to allocate the points of the tree
+    /// up front.
+    fn remaining_hint(&self) -> Option<usize> {
+        None
+    }
+}
+
+#[cfg(feature = "std")]
+pub use reader::PointReader;
+
+#[cfg(feature = "std")]
+mod reader {
+    use super::PointChunks;
+    use crate::FormatScalar;
+    use alloc::{vec, vec::Vec};
+    use core::marker::PhantomData;
+    use std::io::{self, Read};
+
+    /// Reads points stored as consecutive little endian coordinates, e.g. a raw point file,
+    /// from a [Read] in chunks of `chunk_len` points.
+    ///
+    /// ```
+    /// use neighbourhood::{KdTreeBuilder, PointReader};
+    ///
+    /// let bytes: Vec<u8> = [[0.0f32, 1.0], [2.0, 3.0], [4.0, 5.0]]
+    ///     .iter()
+    ///     .flatten()
+    ///     .flat_map(|coordinate| coordinate.to_le_bytes())
+    ///     .collect();
+    /// let reader = PointReader::<_, f32, 2>::new(&bytes[..]).expected_len(3);
+    /// let kd_tree = KdTreeBuilder::new().build_from_chunks(reader).unwrap();
+    /// assert_eq!(kd_tree.nearest(&[2.1, 2.9]).unwrap().1, &[2.0, 3.0]);
+    /// ```
+    #[derive(Debug)]
+    pub struct PointReader<R, T, const N: usize> {
+        reader: R,
+        chunk_len: usize,
+        remaining: Option<usize>,
+        buffer: Vec<u8>,
+        _marker: PhantomData<T>,
+    }
+
+    impl<R: Read, T: FormatScalar, const N: usize> PointReader<R, T, N> {
+        /// Number of points read per chunk by default.
+        pub const DEFAULT_CHUNK_LEN: usize = 4096;
+
+        /// Creates a reader of chunks of [Self::DEFAULT_CHUNK_LEN] points from `reader`.
+        pub fn new(reader: R) -> Self {
+            PointReader {
+                reader,
+                chunk_len: Self::DEFAULT_CHUNK_LEN,
+                remaining: None,
+                buffer: Vec::new(),
+                _marker: PhantomData,
+            }
+        }
+
+        /// Sets the number of points read per chunk, at least 1.
+        pub fn chunk_len(mut self, chunk_len: usize) -> Self {
+            self.chunk_len = chunk_len.max(1);
+            self
+        }
+
+        /// Sets the number of points the reader is expected to yield, e.g. derived from
+        /// the size of a file. Only used as a hint.
+        pub fn expected_len(mut self, len: usize) -> Self {
+            self.remaining = Some(len);
+            self
+        }
+
+        /// Returns the underlying reader.
+        pub fn into_inner(self) -> R {
+            self.reader
+        }
+    }
+
+    impl<R: Read, T: FormatScalar, const N: usize> PointChunks<T, N> for PointReader<R, T, N> {
+        type Error = io::Error;
+
+        fn read_chunk(&mut self, points: &mut Vec<[T; N]>) -> io::Result<usize> {
+            let point_size = N * T::SIZE;
+            if self.buffer.len() != self.chunk_len * point_size {
+                self.buffer = vec![0; self.chunk_len * point_size];
+            }
+
+            let mut filled = 0;
+            while filled < self.buffer.len() {
+                match self.reader.read(&mut self.buffer[filled..]) {
+                    Ok(0) => break,
+                    Ok(read) => filled += read,
+                    Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                    Err(error) => return Err(error),
+                }
+            }
+            if filled % point_size != 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "the input ends within a point",
+                ));
+            }
+
+            let read = filled / point_size;
+            points.extend(self.buffer[..filled].chunks_exact(point_size).map(|bytes| {
+                core::array::from_fn(|i| T::from_le_slice(&bytes[i * T::SIZE..(i + 1) * T::SIZE]))
+            }));
+            self.remaining = self
+                .remaining
+                .map(|remaining| remaining.saturating_sub(read));
+            Ok(read)
+        }
+
+        fn remaining_hint(&self) -> Option<usize> {
+            self.remaining
+        }
+    }
+}
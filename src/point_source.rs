@@ -0,0 +1,179 @@
+/// Read access to the points a [crate::KdIndexTree] is built over. Allows building trees
+/// over data that is not stored as a slice of arrays, e.g. in columns or embedded in
+/// larger structs.
+///
+/// ```
+/// use neighbourhood::{KdIndexTree, Projection};
+///
+/// struct Particle {
+///     mass: f64,
+///     position: [f64; 2],
+/// }
+///
+/// let particles = [
+///     Particle { mass: 1.0, position: [0.0, 0.0] },
+///     Particle { mass: 2.0, position: [3.0, 1.0] },
+/// ];
+/// let kd_index_tree = KdIndexTree::from_source(Projection::new(&particles, |p: &Particle| p.position));
+/// let (_, index) = kd_index_tree.nearest_by_index(&[2.0, 2.0]).unwrap();
+/// assert_eq!(particles[index].mass, 2.0);
+///
+/// let (x, y) = ([0.0, 3.0], [0.0, 1.0]);
+/// let kd_index_tree = KdIndexTree::from_source((&x[..], &y[..]));
+/// assert_eq!(kd_index_tree.nearest_by_index(&[2.0, 2.0]).unwrap().1, 1);
+/// ```
+pub trait PointSource<T, const N: usize> {
+    /// Number of points.
+    fn len(&self) -> usize;
+
+    /// Returns true if there are no points.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Coordinate `axis` of the point at `index`.
+    fn coord(&self, index: usize, axis: usize) -> T;
+
+    /// The point at `index`. Override it if reading a whole point is cheaper than reading
+    /// its coordinates one by one.
+    #[inline]
+    fn point(&self, index: usize) -> [T; N] {
+        core::array::from_fn(|axis| self.coord(index, axis))
+    }
+}
+
+impl<T: Copy, const N: usize, V: AsRef<[[T; N]]> + ?Sized> PointSource<T, N> for &V {
+    #[inline]
+    fn len(&self) -> usize {
+        AsRef::<[[T; N]]>::as_ref(*self).len()
+    }
+
+    #[inline]
+    fn coord(&self, index: usize, axis: usize) -> T {
+        AsRef::<[[T; N]]>::as_ref(*self)[index][axis]
+    }
+
+    #[inline]
+    fn point(&self, index: usize) -> [T; N] {
+        AsRef::<[[T; N]]>::as_ref(*self)[index]
+    }
+}
+
+/// Points owned by a tree, see [crate::OwnedKdIndexTree].
+#[derive(Debug, Clone)]
+pub struct OwnedPoints<D>(D);
+
+impl<D> OwnedPoints<D> {
+    /// Wraps the points `data`.
+    pub fn new(data: D) -> Self {
+        Self(data)
+    }
+
+    /// Returns a read-only reference to the points.
+    pub fn data(&self) -> &D {
+        &self.0
+    }
+
+    /// Returns the points.
+    pub fn into_data(self) -> D {
+        self.0
+    }
+}
+
+impl<T: Copy, const N: usize, D: AsRef<[[T; N]]>> PointSource<T, N> for OwnedPoints<D> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.0.as_ref().len()
+    }
+
+    #[inline]
+    fn coord(&self, index: usize, axis: usize) -> T {
+        self.0.as_ref()[index][axis]
+    }
+
+    #[inline]
+    fn point(&self, index: usize) -> [T; N] {
+        self.0.as_ref()[index]
+    }
+}
+
+/// Struct of arrays with one column per axis. Columns of different lengths are cut to
+/// the shortest one.
+impl<T: Copy, const N: usize> PointSource<T, N> for [&[T]; N] {
+    #[inline]
+    fn len(&self) -> usize {
+        self.iter().map(|column| column.len()).min().unwrap_or(0)
+    }
+
+    #[inline]
+    fn coord(&self, index: usize, axis: usize) -> T {
+        self[axis][index]
+    }
+}
+
+macro_rules! impl_point_source_for_columns {
+    ($n:literal; $($axis:tt),+) => {
+        /// Struct of arrays with one column per axis. Columns of different lengths are
+        /// cut to the shortest one.
+        impl<T: Copy> PointSource<T, $n> for ($(impl_point_source_for_columns!(@column $axis),)+) {
+            #[inline]
+            fn len(&self) -> usize {
+                let mut len = usize::MAX;
+                $(len = len.min(self.$axis.len());)+
+                len
+            }
+
+            #[inline]
+            fn coord(&self, index: usize, axis: usize) -> T {
+                match axis {
+                    $($axis => self.$axis[index],)+
+                    _ => panic!("axis {axis} out of range"),
+                }
+            }
+
+            #[inline]
+            fn point(&self, index: usize) -> [T; $n] {
+                [$(self.$axis[index]),+]
+            }
+        }
+    };
+    (@column $axis:tt) => { &[T] };
+}
+
+impl_point_source_for_columns!(2; 0, 1);
+impl_point_source_for_columns!(3; 0, 1, 2);
+impl_point_source_for_columns!(4; 0, 1, 2, 3);
+
+/// Points computed from a slice of items by a projection, e.g. positions embedded in
+/// larger structs.
+#[derive(Debug, Copy, Clone)]
+pub struct Projection<'s, S, F> {
+    items: &'s [S],
+    project: F,
+}
+
+impl<'s, S, F> Projection<'s, S, F> {
+    /// Uses `project(&items[index])` as the point at `index`.
+    pub fn new(items: &'s [S], project: F) -> Self {
+        Self { items, project }
+    }
+}
+
+impl<'s, S, T: Copy, const N: usize, F: Fn(&S) -> [T; N]> PointSource<T, N>
+    for Projection<'s, S, F>
+{
+    #[inline]
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    #[inline]
+    fn coord(&self, index: usize, axis: usize) -> T {
+        (self.project)(&self.items[index])[axis]
+    }
+
+    #[inline]
+    fn point(&self, index: usize) -> [T; N] {
+        (self.project)(&self.items[index])
+    }
+}
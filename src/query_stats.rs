@@ -0,0 +1,97 @@
+//! Counters describing the work done by queries. Only collected with the `query-stats`
+//! feature, otherwise all hooks compile to nothing.
+
+/// Work done by the queries executed on the current thread since the last call to
+/// [take_query_stats].
+#[cfg(feature = "query-stats")]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct QueryStats {
+    /// Number of subtrees entered, including leaves.
+    pub nodes_visited: usize,
+    /// Number of subtrees skipped because they cannot contribute to the result.
+    pub subtrees_pruned: usize,
+    /// Number of distances computed between the query point and a point of the tree.
+    pub distance_evaluations: usize,
+    /// Number of subtrees scanned by brute force.
+    pub leaf_scans: usize,
+    /// Deepest recursion reached, the root having depth 1.
+    pub max_depth: usize,
+}
+
+#[cfg(feature = "query-stats")]
+std::thread_local! {
+    static STATS: core::cell::Cell<QueryStats> = const {
+        core::cell::Cell::new(QueryStats {
+            nodes_visited: 0,
+            subtrees_pruned: 0,
+            distance_evaluations: 0,
+            leaf_scans: 0,
+            max_depth: 0,
+        })
+    };
+    static DEPTH: core::cell::Cell<usize> = const { core::cell::Cell::new(0) };
+}
+
+/// Returns the statistics of all queries executed on the current thread since the last
+/// call and resets them. Call it once before and once after a query to obtain the
+/// statistics of that single query.
+#[cfg(feature = "query-stats")]
+pub fn take_query_stats() -> QueryStats {
+    STATS.with(|stats| stats.take())
+}
+
+#[cfg(feature = "query-stats")]
+#[inline]
+fn update(f: impl FnOnce(&mut QueryStats)) {
+    STATS.with(|stats| {
+        let mut value = stats.get();
+        f(&mut value);
+        stats.set(value);
+    });
+}
+
+/// Leaves the node entered by [enter_node] when dropped.
+pub(crate) struct NodeGuard(());
+
+#[cfg(feature = "query-stats")]
+impl Drop for NodeGuard {
+    fn drop(&mut self) {
+        DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
+/// Records that a subtree is entered. The returned guard must be kept alive until the
+/// subtree is left.
+#[inline(always)]
+pub(crate) fn enter_node() -> NodeGuard {
+    #[cfg(feature = "query-stats")]
+    {
+        let depth = DEPTH.with(|depth| {
+            depth.set(depth.get() + 1);
+            depth.get()
+        });
+        update(|stats| {
+            stats.nodes_visited += 1;
+            stats.max_depth = stats.max_depth.max(depth);
+        });
+    }
+    NodeGuard(())
+}
+
+#[inline(always)]
+pub(crate) fn subtree_pruned() {
+    #[cfg(feature = "query-stats")]
+    update(|stats| stats.subtrees_pruned += 1);
+}
+
+#[inline(always)]
+pub(crate) fn distance_evaluation() {
+    #[cfg(feature = "query-stats")]
+    update(|stats| stats.distance_evaluations += 1);
+}
+
+#[inline(always)]
+pub(crate) fn leaf_scan() {
+    #[cfg(feature = "query-stats")]
+    update(|stats| stats.leaf_scans += 1);
+}
@@ -0,0 +1,265 @@
+//! Serde support, enabled by the `serde` feature.
+//!
+//! A [KdTree] is serialized together with its data. A [KdIndexTree] only serializes its
+//! indices, which are loaded as [SerializedKdIndexTree] and re-attached to the data with
+//! [KdIndexTree::from_serialized].
+
+use alloc::vec::Vec;
+use core::{fmt, marker::PhantomData};
+use num_traits::Float;
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    ser::{SerializeSeq, SerializeStruct, SerializeTuple},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{
+    bounds::BoundingBoxes, knn_radii::TreeId, prealloc_capacity, split::SplitLayout,
+    top_levels::TopLevels, Coordinate, GenericKdTree, IndexRangeError, KdIndexTree, KdTree,
+    PointSource, SplitAxis, TreeIndex, ValidationError,
+};
+
+impl<'a, T: Float + Coordinate + Serialize, const N: usize, D: AsRef<[[T; N]]>> Serialize
+    for GenericKdTree<'a, T, N, D>
+{
+    /// Views are serialized like the [KdTree] they borrow from.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("KdTree", 5)?;
+        state.serialize_field("data", &Points(self.data()))?;
+        state.serialize_field("brute_force_size", &self.brute_force_size)?;
+        state.serialize_field("leaf_size", &self.leaf_size)?;
+        state.serialize_field("layout", &self.layout)?;
+        state.serialize_field("split_axis", &self.builder.split_axis)?;
+        state.end()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(
+    rename = "KdTree",
+    bound(deserialize = "T: Float + Coordinate + Deserialize<'de>")
+)]
+struct KdTreeFields<T, const N: usize> {
+    #[serde(deserialize_with = "deserialize_points")]
+    data: Vec<[T; N]>,
+    brute_force_size: usize,
+    leaf_size: usize,
+    layout: SplitLayout<'static>,
+    /// Axis policy used to rebuild the tree on [Extend::extend]. Missing in trees
+    /// serialized by earlier versions.
+    #[serde(default)]
+    split_axis: Option<SplitAxis>,
+}
+
+impl<'de, T: Float + Coordinate + Deserialize<'de>, const N: usize> Deserialize<'de>
+    for KdTree<T, N>
+{
+    /// Restores the tree without running the construction again. Only the split positions
+    /// and axes are checked to be in range, use [KdTree::validate] to check the
+    /// partitioning.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let fields = KdTreeFields::<T, N>::deserialize(deserializer)?;
+        fields
+            .layout
+            .check::<N>(fields.data.len(), fields.leaf_size)
+            .map_err(de::Error::custom)?;
+        let mut kd_tree = KdTree::from_parts(
+            fields.data,
+            fields.brute_force_size,
+            fields.layout,
+            fields.leaf_size,
+        );
+        if let Some(split_axis) = fields.split_axis {
+            kd_tree.builder = kd_tree.builder.split_axis(split_axis);
+        }
+        kd_tree.rebuild = |builder, data| builder.build(data);
+        Ok(kd_tree)
+    }
+}
+
+/// The serialized form of a [KdIndexTree] without its data. Obtained by deserializing a
+/// serialized KdIndexTree and turned back into a tree by [KdIndexTree::from_serialized].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename = "KdIndexTree")]
+pub struct SerializedKdIndexTree {
+    indices: Vec<usize>,
+    brute_force_size: usize,
+    leaf_size: usize,
+    layout: SplitLayout<'static>,
+    checksum: u64,
+}
+
+impl<'a, T: Float + Coordinate, const N: usize, I: TreeIndex, P: PointSource<T, N>> Serialize
+    for KdIndexTree<'a, T, N, I, P>
+{
+    /// The indices are serialized independent of the index type of the tree.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("KdIndexTree", 5)?;
+        state.serialize_field("indices", &Indices(&self.indices))?;
+        state.serialize_field("brute_force_size", &self.brute_force_size)?;
+        state.serialize_field("leaf_size", &self.leaf_size)?;
+        state.serialize_field("layout", &self.layout)?;
+        state.serialize_field("checksum", &checksum(&self.data))?;
+        state.end()
+    }
+}
+
+impl<'a, T: Float + Coordinate, const N: usize> KdIndexTree<'a, T, N> {
+    /// Re-attaches a deserialized KdIndexTree to `data`, which must be the data the tree
+    /// was built for. Fails if the length or the checksum of `data` differ, or if the
+    /// indices are not a permutation of the data indices.
+    pub fn from_serialized(
+        data: &'a [[T; N]],
+        serialized: SerializedKdIndexTree,
+    ) -> Result<Self, ValidationError> {
+        Self::try_from_serialized(data, serialized)
+    }
+}
+
+impl<'a, T: Float + Coordinate, const N: usize, I: TreeIndex, P: PointSource<T, N>>
+    KdIndexTree<'a, T, N, I, P>
+{
+    /// Like [KdIndexTree::from_serialized], but reads the points from any [PointSource]
+    /// and stores the indices as `I`. Also fails if `data` has more points than `I` can
+    /// address.
+    pub fn try_from_serialized(
+        data: P,
+        serialized: SerializedKdIndexTree,
+    ) -> Result<Self, ValidationError> {
+        if serialized.indices.len() != data.len() || serialized.checksum != checksum(&data) {
+            return Err(ValidationError::Data);
+        }
+        serialized
+            .layout
+            .check::<N>(data.len(), serialized.leaf_size)?;
+        if IndexRangeError::check::<I>(data.len()).is_err()
+            || serialized.indices.iter().any(|index| *index >= data.len())
+        {
+            return Err(ValidationError::Indices);
+        }
+        let kd_index_tree = Self {
+            indices: serialized.indices.into_iter().map(I::from_usize).collect(),
+            data,
+            brute_force_size: serialized.brute_force_size,
+            layout: serialized.layout,
+            leaf_size: serialized.leaf_size,
+            boxes: BoundingBoxes::none(),
+            top_levels: TopLevels::none(),
+            id: TreeId::new(),
+            _marker: PhantomData,
+        };
+        kd_index_tree.validate_indices()?;
+        Ok(kd_index_tree)
+    }
+}
+
+/// FNV-1a hash over the bit patterns of all coordinates.
+fn checksum<T: Float, const N: usize>(data: &impl PointSource<T, N>) -> u64 {
+    let mut hash = 0xcbf29ce484222325u64;
+    let mut add = |value: u64| {
+        for byte in value.to_le_bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    };
+    add(N as u64);
+    for index in 0..data.len() {
+        for coordinate in data.point(index) {
+            let (mantissa, exponent, sign) = coordinate.integer_decode();
+            add(mantissa);
+            add(((exponent as u16 as u64) << 8) | (sign as u8 as u64));
+        }
+    }
+    hash
+}
+
+/// Serializes indices of any [TreeIndex] as `usize`.
+struct Indices<'a, I>(&'a [I]);
+
+impl<'a, I: TreeIndex> Serialize for Indices<'a, I> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(|index| index.to_usize()))
+    }
+}
+
+/// Serializes points as a sequence of tuples, serde only supports arrays up to a
+/// length of 32.
+struct Points<'a, T, const N: usize>(&'a [[T; N]]);
+
+impl<'a, T: Serialize, const N: usize> Serialize for Points<'a, T, N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for point in self.0 {
+            seq.serialize_element(&Point(point))?;
+        }
+        seq.end()
+    }
+}
+
+struct Point<'a, T, const N: usize>(&'a [T; N]);
+
+impl<'a, T: Serialize, const N: usize> Serialize for Point<'a, T, N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tuple = serializer.serialize_tuple(N)?;
+        for coordinate in self.0 {
+            tuple.serialize_element(coordinate)?;
+        }
+        tuple.end()
+    }
+}
+
+fn deserialize_points<'de, D, T, const N: usize>(deserializer: D) -> Result<Vec<[T; N]>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Float + Deserialize<'de>,
+{
+    struct PointsVisitor<T, const N: usize>(PhantomData<T>);
+
+    impl<'de, T: Float + Deserialize<'de>, const N: usize> Visitor<'de> for PointsVisitor<T, N> {
+        type Value = Vec<[T; N]>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            write!(formatter, "a sequence of points")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            // Like serde, do not trust the size hint for more than a small allocation.
+            let capacity = prealloc_capacity::<[T; N]>(seq.size_hint().unwrap_or(0));
+            let mut points = Vec::with_capacity(capacity);
+            while let Some(OwnedPoint(point)) = seq.next_element()? {
+                points.push(point);
+            }
+            Ok(points)
+        }
+    }
+
+    deserializer.deserialize_seq(PointsVisitor(PhantomData))
+}
+
+struct OwnedPoint<T, const N: usize>([T; N]);
+
+impl<'de, T: Float + Deserialize<'de>, const N: usize> Deserialize<'de> for OwnedPoint<T, N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct PointVisitor<T, const N: usize>(PhantomData<T>);
+
+        impl<'de, T: Float + Deserialize<'de>, const N: usize> Visitor<'de> for PointVisitor<T, N> {
+            type Value = OwnedPoint<T, N>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(formatter, "a point with {N} coordinates")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut point = [T::zero(); N];
+                for (i, coordinate) in point.iter_mut().enumerate() {
+                    *coordinate = seq
+                        .next_element()?
+                        .ok_or_else(|| de::Error::invalid_length(i, &self))?;
+                }
+                Ok(OwnedPoint(point))
+            }
+        }
+
+        deserializer.deserialize_tuple(N, PointVisitor(PhantomData))
+    }
+}
@@ -0,0 +1,80 @@
+//! Vectorised brute force leaf scans for `f32` and `f64` points.
+//!
+//! The points of a leaf are transposed on the fly into one vector per axis, so that the
+//! distances of several points are computed at once. Every lane performs the same
+//! operations in the same order as [crate::distance], hence the results are identical.
+
+use crate::{distance, query_stats, slice_distance};
+use wide::{f32x8, f64x4};
+
+macro_rules! leaf_distances {
+    ($name:ident, $float:ty, $simd:ty, $lanes:literal) => {
+        #[inline]
+        pub(crate) fn $name<const N: usize>(
+            point: &[$float; N],
+            len: usize,
+            leaf_point: impl Fn(usize) -> [$float; N],
+            mut f: impl FnMut(usize, $float),
+        ) {
+            let mut position = 0;
+            while position + $lanes <= len {
+                let points: [[$float; N]; $lanes] =
+                    core::array::from_fn(|lane| leaf_point(position + lane));
+                let mut sum = <$simd>::ZERO;
+                for i in 0..N {
+                    let axis =
+                        <$simd>::from(core::array::from_fn::<_, $lanes, _>(|lane| points[lane][i]));
+                    let offset = <$simd>::splat(point[i]) - axis;
+                    sum += offset * offset;
+                }
+                for (lane, dst) in sum.sqrt().to_array().into_iter().enumerate() {
+                    query_stats::distance_evaluation();
+                    f(position + lane, dst);
+                }
+                position += $lanes;
+            }
+
+            for position in position..len {
+                f(position, distance(point, &leaf_point(position)));
+            }
+        }
+    };
+}
+
+macro_rules! slice_leaf_distances {
+    ($name:ident, $float:ty, $simd:ty, $lanes:literal) => {
+        #[inline]
+        pub(crate) fn $name(point: &[$float], leaf: &[$float], mut f: impl FnMut(usize, $float)) {
+            let dim = point.len();
+            let len = leaf.len() / dim;
+            let mut position = 0;
+            while position + $lanes <= len {
+                let mut sum = <$simd>::ZERO;
+                for i in 0..dim {
+                    let axis = <$simd>::from(core::array::from_fn::<_, $lanes, _>(|lane| {
+                        leaf[(position + lane) * dim + i]
+                    }));
+                    let offset = <$simd>::splat(point[i]) - axis;
+                    sum += offset * offset;
+                }
+                for (lane, dst) in sum.sqrt().to_array().into_iter().enumerate() {
+                    query_stats::distance_evaluation();
+                    f(position + lane, dst);
+                }
+                position += $lanes;
+            }
+
+            for position in position..len {
+                f(
+                    position,
+                    slice_distance(point, &leaf[position * dim..(position + 1) * dim]),
+                );
+            }
+        }
+    };
+}
+
+leaf_distances!(leaf_distances_f32, f32, f32x8, 8);
+leaf_distances!(leaf_distances_f64, f64, f64x4, 4);
+slice_leaf_distances!(slice_leaf_distances_f32, f32, f32x8, 8);
+slice_leaf_distances!(slice_leaf_distances_f64, f64, f64x4, 4);
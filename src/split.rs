@@ -0,0 +1,280 @@
+use crate::ValidationError;
+use alloc::borrow::Cow;
+use num_traits::Float;
+
+/// Determines how the split axis of every node is chosen during construction.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SplitAxis {
+    /// Cycle through the axes with increasing depth. Requires no additional memory.
+    #[default]
+    RoundRobin,
+    /// Split along the axis on which the points of a subtree have the largest extent.
+    /// Stores one byte per point.
+    MaxSpread,
+    /// Split along the axis on which the points of a subtree have the largest variance.
+    /// Stores one byte per point.
+    MaxVariance,
+}
+
+impl SplitAxis {
+    /// Chooses the split axis for a subtree consisting of `points`, whose coordinates
+    /// are returned by `coordinate(point, axis)`.
+    /// Must not be called for [SplitAxis::RoundRobin].
+    #[allow(clippy::needless_range_loop)]
+    pub(crate) fn select<E, T: Float, const N: usize>(
+        self,
+        points: &[E],
+        coordinate: &impl Fn(&E, usize) -> T,
+    ) -> usize {
+        let mut score = [T::zero(); N];
+        match self {
+            SplitAxis::RoundRobin => unreachable!(),
+            SplitAxis::MaxSpread => {
+                let mut min = [T::infinity(); N];
+                let mut max = [T::neg_infinity(); N];
+                for pt in points {
+                    for i in 0..N {
+                        min[i] = min[i].min(coordinate(pt, i));
+                        max[i] = max[i].max(coordinate(pt, i));
+                    }
+                }
+                for i in 0..N {
+                    score[i] = max[i] - min[i];
+                }
+            }
+            SplitAxis::MaxVariance => {
+                let mut count = T::zero();
+                let mut mean = [T::zero(); N];
+                for pt in points {
+                    count = count + T::one();
+                    for i in 0..N {
+                        mean[i] = mean[i] + coordinate(pt, i);
+                    }
+                }
+                for pt in points {
+                    for i in 0..N {
+                        score[i] = score[i] + (coordinate(pt, i) - mean[i] / count).powi(2);
+                    }
+                }
+            }
+        }
+
+        let mut axis = 0;
+        for i in 1..N {
+            if score[i] > score[axis] {
+                axis = i;
+            }
+        }
+        axis
+    }
+}
+
+/// Split information stored by a tree, see [Splits].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) enum SplitLayout<'a> {
+    /// Median splits with round robin axes.
+    RoundRobin,
+    /// Median splits, the axis of every node is stored at the position of its split point.
+    Axes(Cow<'a, [u8]>),
+    /// Splits of [crate::SplitStrategy::BucketLeaf], whose positions follow from the
+    /// length of the subtree and `leaf_size`. The axis of every node is stored at the
+    /// position of its split point.
+    BucketLeaf {
+        axes: Cow<'a, [u8]>,
+        leaf_size: usize,
+    },
+    /// Arbitrary splits. The axis of every node is stored at the position of its split
+    /// point, the splits of the subtrees larger than the leaf size are stored in
+    /// pre-order.
+    Explicit {
+        axes: Cow<'a, [u8]>,
+        splits: Cow<'a, [ExplicitSplit]>,
+    },
+}
+
+/// Stored split of a subtree in [SplitLayout::Explicit].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(C)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct ExplicitSplit {
+    /// Position of the split point relative to the subtree.
+    pub(crate) split_index: u32,
+    /// Number of stored splits of the left subtree, which directly follow this one.
+    pub(crate) left_splits: u32,
+}
+
+/// Split position of a [crate::SplitStrategy::BucketLeaf] subtree of length `len`, which
+/// is the size of the largest perfect tree of full leaves that is smaller than `len`.
+pub(crate) fn bucket_leaf_split_index(len: usize, leaf_size: usize) -> usize {
+    let leaf_size = leaf_size.max(1);
+    let mut perfect_size = leaf_size;
+    while 2 * perfect_size + 1 < len {
+        perfect_size = 2 * perfect_size + 1;
+    }
+    perfect_size
+}
+
+impl<'a> SplitLayout<'a> {
+    /// Returns true if the stored split information covers exactly `len` points.
+    pub(crate) fn has_len(&self, len: usize) -> bool {
+        match self {
+            SplitLayout::RoundRobin => true,
+            SplitLayout::Axes(axes) => axes.len() == len,
+            SplitLayout::BucketLeaf { axes, .. } => axes.len() == len,
+            SplitLayout::Explicit { axes, splits } => axes.len() == len && splits.len() <= len,
+        }
+    }
+
+    /// Checks that the split positions and axes of every subtree of a tree of `len` points
+    /// that is larger than `leaf_size` are in range, so that queries cannot panic. Does
+    /// not check the partitioning, see [crate::KdTree::validate].
+    pub(crate) fn check<const N: usize>(
+        &self,
+        len: usize,
+        leaf_size: usize,
+    ) -> Result<(), ValidationError> {
+        if !self.has_len(len) {
+            return Err(ValidationError::Layout { position: 0 });
+        }
+        // Explicit splits may be degenerate, so the subtrees are kept on a stack instead
+        // of recursing.
+        let mut subtrees = alloc::vec![(0, len, self.splits())];
+        while let Some((offset, len, splits)) = subtrees.pop() {
+            if len <= leaf_size.max(1) {
+                continue;
+            }
+            let (split_index, _, splits1, splits2) = splits
+                .checked_split::<N>(len)
+                .ok_or(ValidationError::Layout { position: offset })?;
+            subtrees.push((offset, split_index, splits1));
+            subtrees.push((offset + split_index + 1, len - split_index - 1, splits2));
+        }
+        Ok(())
+    }
+
+    /// Returns a layout borrowing the split information of `self`.
+    pub(crate) fn borrowed(&self) -> SplitLayout<'_> {
+        match self {
+            SplitLayout::RoundRobin => SplitLayout::RoundRobin,
+            SplitLayout::Axes(axes) => SplitLayout::Axes(Cow::Borrowed(axes)),
+            SplitLayout::BucketLeaf { axes, leaf_size } => SplitLayout::BucketLeaf {
+                axes: Cow::Borrowed(axes),
+                leaf_size: *leaf_size,
+            },
+            SplitLayout::Explicit { axes, splits } => SplitLayout::Explicit {
+                axes: Cow::Borrowed(axes),
+                splits: Cow::Borrowed(splits),
+            },
+        }
+    }
+
+    pub(crate) fn splits(&self) -> Splits<'_> {
+        match self {
+            SplitLayout::RoundRobin => Splits::RoundRobin(0),
+            SplitLayout::Axes(axes) => Splits::Stored(axes),
+            SplitLayout::BucketLeaf { axes, leaf_size } => Splits::BucketLeaf {
+                axes,
+                leaf_size: *leaf_size,
+            },
+            SplitLayout::Explicit { axes, splits } => Splits::Explicit { axes, splits },
+        }
+    }
+}
+
+/// Describes how the nodes of an implicitly stored subtree are split.
+#[derive(Debug, Copy, Clone)]
+pub(crate) enum Splits<'a> {
+    /// The split axis is derived from the depth of the node.
+    RoundRobin(usize),
+    /// The split axis of each node is stored at the position of its split point.
+    Stored(&'a [u8]),
+    /// The split axis is stored, the split position is derived from the length, see
+    /// [SplitLayout::BucketLeaf].
+    BucketLeaf { axes: &'a [u8], leaf_size: usize },
+    /// The split axis and the split position are stored, see [SplitLayout::Explicit].
+    /// `splits` starts with the split of the subtree.
+    Explicit {
+        axes: &'a [u8],
+        splits: &'a [ExplicitSplit],
+    },
+}
+
+impl<'a> Splits<'a> {
+    /// Like [Self::split], but returns `None` instead of panicking if the stored split
+    /// position or axis is out of range.
+    pub(crate) fn checked_split<const N: usize>(
+        self,
+        len: usize,
+    ) -> Option<(usize, usize, Self, Self)> {
+        let split_index = match self {
+            Splits::BucketLeaf { leaf_size, .. } => bucket_leaf_split_index(len, leaf_size),
+            Splits::Explicit { splits, .. } => {
+                let split = splits.first()?;
+                if split.left_splits as usize >= splits.len() {
+                    return None;
+                }
+                split.split_index as usize
+            }
+            _ => len / 2,
+        };
+        if split_index >= len {
+            return None;
+        }
+        let split = self.split::<N>(len);
+        (split.1 < N).then_some(split)
+    }
+
+    /// Splits a subtree of length `len`. Returns the position of the split point, the
+    /// split axis and the splits of both child subtrees.
+    #[inline]
+    pub(crate) fn split<const N: usize>(self, len: usize) -> (usize, usize, Self, Self) {
+        match self {
+            Splits::RoundRobin(row) => {
+                let next = Splits::RoundRobin((row + 1) % N);
+                (len / 2, row, next, next)
+            }
+            Splits::Stored(axes) => {
+                let split_index = len / 2;
+                (
+                    split_index,
+                    axes[split_index] as usize,
+                    Splits::Stored(&axes[..split_index]),
+                    Splits::Stored(&axes[(split_index + 1)..]),
+                )
+            }
+            Splits::BucketLeaf { axes, leaf_size } => {
+                let split_index = bucket_leaf_split_index(len, leaf_size);
+                (
+                    split_index,
+                    axes[split_index] as usize,
+                    Splits::BucketLeaf {
+                        axes: &axes[..split_index],
+                        leaf_size,
+                    },
+                    Splits::BucketLeaf {
+                        axes: &axes[(split_index + 1)..],
+                        leaf_size,
+                    },
+                )
+            }
+            Splits::Explicit { axes, splits } => {
+                let split_index = splits[0].split_index as usize;
+                let left_end = 1 + splits[0].left_splits as usize;
+                (
+                    split_index,
+                    axes[split_index] as usize,
+                    Splits::Explicit {
+                        axes: &axes[..split_index],
+                        splits: &splits[1..left_end],
+                    },
+                    Splits::Explicit {
+                        axes: &axes[(split_index + 1)..],
+                        splits: &splits[left_end..],
+                    },
+                )
+            }
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use alloc::{vec, vec::Vec};
+use core::fmt;
+use num_traits::Float;
+
+use crate::{split::Splits, Coordinate};
+
+/// Structure of a K-d Tree as seen by the queries, see [crate::KdTree::stats] and
+/// [crate::KdIndexTree::stats].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeStats<T> {
+    /// Number of levels of the tree, including the leaves.
+    pub depth: usize,
+    /// Number of non-empty subtrees scanned by brute force.
+    pub leaf_count: usize,
+    /// `leaf_sizes[n]` is the number of leaves holding `n` points.
+    pub leaf_sizes: Vec<usize>,
+    /// Sum of the volumes of the bounding boxes of all subtrees on each level.
+    pub level_volumes: Vec<T>,
+}
+
+/// Reason why [crate::KdTree::validate] or [crate::KdIndexTree::validate] failed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ValidationError {
+    /// The split point at `position` does not partition its subtree along `axis`.
+    Partition { position: usize, axis: usize },
+    /// The stored split position or axis of the subtree starting at `position` is out
+    /// of range.
+    Layout { position: usize },
+    /// The indices of a KdIndexTree are not a permutation of the indices of its data.
+    Indices,
+    /// The data does not match the data a deserialized KdIndexTree was built for.
+    Data,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::Partition { position, axis } => write!(
+                f,
+                "split point at position {position} does not partition its subtree along axis {axis}"
+            ),
+            ValidationError::Layout { position } => write!(
+                f,
+                "split information of the subtree at position {position} is out of range"
+            ),
+            ValidationError::Indices => write!(f, "indices are not a permutation of the data"),
+            ValidationError::Data => write!(f, "data does not match the data of the tree"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ValidationError {}
+
+/// Collects the [TreeStats] of the `len` points returned by `point(position)`, split
+/// according to `splits` until at most `leaf_scan_size` points are left.
+pub(crate) fn collect_tree_stats<T: Float, const N: usize>(
+    len: usize,
+    point: &impl Fn(usize) -> [T; N],
+    splits: Splits,
+    leaf_scan_size: usize,
+) -> TreeStats<T> {
+    let mut stats = TreeStats {
+        depth: 0,
+        leaf_count: 0,
+        leaf_sizes: vec![],
+        level_volumes: vec![],
+    };
+    stats_recursive(0, len, 0, point, splits, leaf_scan_size, &mut stats);
+    stats
+}
+
+fn stats_recursive<T: Float, const N: usize>(
+    offset: usize,
+    len: usize,
+    level: usize,
+    point: &impl Fn(usize) -> [T; N],
+    splits: Splits,
+    leaf_scan_size: usize,
+    stats: &mut TreeStats<T>,
+) -> [[T; N]; 2] {
+    let mut bounds = [[T::infinity(); N], [T::neg_infinity(); N]];
+    if len == 0 {
+        return bounds;
+    }
+
+    if len <= leaf_scan_size.max(1) {
+        stats.leaf_count += 1;
+        if stats.leaf_sizes.len() <= len {
+            stats.leaf_sizes.resize(len + 1, 0);
+        }
+        stats.leaf_sizes[len] += 1;
+        for position in offset..(offset + len) {
+            extend(&mut bounds, &point(position));
+        }
+    } else {
+        let (split_index, _, splits1, splits2) = splits.split::<N>(len);
+        let bounds1 = stats_recursive(
+            offset,
+            split_index,
+            level + 1,
+            point,
+            splits1,
+            leaf_scan_size,
+            stats,
+        );
+        let bounds2 = stats_recursive(
+            offset + split_index + 1,
+            len - split_index - 1,
+            level + 1,
+            point,
+            splits2,
+            leaf_scan_size,
+            stats,
+        );
+        extend(&mut bounds, &point(offset + split_index));
+        for child in [bounds1, bounds2] {
+            extend(&mut bounds, &child[0]);
+            extend(&mut bounds, &child[1]);
+        }
+    }
+
+    stats.depth = stats.depth.max(level + 1);
+    if stats.level_volumes.len() <= level {
+        stats.level_volumes.resize(level + 1, T::zero());
+    }
+    let volume = (0..N).fold(T::one(), |volume, i| volume * (bounds[1][i] - bounds[0][i]));
+    stats.level_volumes[level] = stats.level_volumes[level] + volume;
+    bounds
+}
+
+#[allow(clippy::needless_range_loop)]
+fn extend<T: Float, const N: usize>(bounds: &mut [[T; N]; 2], point: &[T; N]) {
+    for i in 0..N {
+        bounds[0][i] = bounds[0][i].min(point[i]);
+        bounds[1][i] = bounds[1][i].max(point[i]);
+    }
+}
+
+/// Checks that every split point of the `len` points returned by `point(position)`
+/// partitions its subtree, down to subtrees of `leaf_size` points.
+pub(crate) fn validate_partition<T: Coordinate, const N: usize>(
+    len: usize,
+    point: &impl Fn(usize) -> [T; N],
+    splits: Splits,
+    leaf_size: usize,
+) -> Result<(), ValidationError> {
+    validate_recursive(0, len, point, splits, leaf_size)
+}
+
+fn validate_recursive<T: Coordinate, const N: usize>(
+    offset: usize,
+    len: usize,
+    point: &impl Fn(usize) -> [T; N],
+    splits: Splits,
+    leaf_size: usize,
+) -> Result<(), ValidationError> {
+    if len <= leaf_size.max(1) {
+        return Ok(());
+    }
+
+    let (split_index, axis, splits1, splits2) = splits
+        .checked_split::<N>(len)
+        .ok_or(ValidationError::Layout { position: offset })?;
+    let split_value = point(offset + split_index)[axis];
+    let error = ValidationError::Partition {
+        position: offset + split_index,
+        axis,
+    };
+    for position in offset..(offset + split_index) {
+        if !point(position)[axis]
+            .partial_cmp(&split_value)
+            .is_some_and(|ordering| ordering.is_le())
+        {
+            return Err(error);
+        }
+    }
+    for position in (offset + split_index + 1)..(offset + len) {
+        if !point(position)[axis]
+            .partial_cmp(&split_value)
+            .is_some_and(|ordering| ordering.is_ge())
+        {
+            return Err(error);
+        }
+    }
+
+    validate_recursive(offset, split_index, point, splits1, leaf_size)?;
+    validate_recursive(
+        offset + split_index + 1,
+        len - split_index - 1,
+        point,
+        splits2,
+        leaf_size,
+    )
+}
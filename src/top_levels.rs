@@ -0,0 +1,179 @@
+use alloc::vec::Vec;
+
+use crate::{split::Splits, Coordinate, PointSource, TreeIndex};
+
+/// Copy of a split point of the top levels together with its index, which is the position
+/// in the data for a [crate::KdTree] and the index of the point for a
+/// [crate::KdIndexTree].
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct TopNode<T, const N: usize> {
+    point: [T; N],
+    index: usize,
+}
+
+/// The split points of the top levels of a tree stored contiguously in breadth first
+/// order. The children of the node at `i` are stored at `2 * i + 1` and `2 * i + 2`, so
+/// the first levels of every query touch only a few cache lines instead of points spread
+/// across the whole data.
+#[derive(Debug, Clone)]
+pub(crate) struct TopLevels<T, const N: usize> {
+    nodes: Vec<Option<TopNode<T, N>>>,
+}
+
+impl<T: Coordinate, const N: usize> TopLevels<T, N> {
+    /// The most levels that can be stored.
+    pub(crate) const MAX_LEVELS: usize = 24;
+
+    /// No levels are stored, queries read all split points from the data.
+    pub(crate) fn none() -> Self {
+        TopLevels { nodes: Vec::new() }
+    }
+
+    /// Stores the split points of the top `levels` levels of a tree with `len` points and
+    /// leaves of up to `leaf_size` points. The split point at `position` is
+    /// `point(position)` with index `index(position)`.
+    pub(crate) fn new(
+        len: usize,
+        point: &impl Fn(usize) -> [T; N],
+        index: &impl Fn(usize) -> usize,
+        splits: Splits,
+        leaf_size: usize,
+        levels: usize,
+    ) -> Self {
+        let mut nodes = Vec::new();
+        let subtree = Subtree {
+            offset: 0,
+            len,
+            splits,
+        };
+        let limits = Limits {
+            nodes: (1usize << levels.min(Self::MAX_LEVELS)) - 1,
+            leaf_size: leaf_size.max(1),
+        };
+        fill_recursive(&mut nodes, 0, subtree, point, index, &limits);
+        TopLevels { nodes }
+    }
+
+    pub(crate) fn splits<'a>(&'a self, splits: Splits<'a>) -> TopSplits<'a, T, N> {
+        TopSplits {
+            splits,
+            nodes: &self.nodes,
+            node: 0,
+        }
+    }
+}
+
+struct Limits {
+    /// Number of nodes in the stored levels of a complete tree.
+    nodes: usize,
+    leaf_size: usize,
+}
+
+struct Subtree<'a> {
+    offset: usize,
+    len: usize,
+    splits: Splits<'a>,
+}
+
+/// Stores the split point of `subtree` at `node` and recurses into its children. Leaves
+/// are not split by the queries and have no split point.
+fn fill_recursive<T: Coordinate, const N: usize>(
+    nodes: &mut Vec<Option<TopNode<T, N>>>,
+    node: usize,
+    subtree: Subtree,
+    point: &impl Fn(usize) -> [T; N],
+    index: &impl Fn(usize) -> usize,
+    limits: &Limits,
+) {
+    if node >= limits.nodes || subtree.len <= limits.leaf_size {
+        return;
+    }
+    if node >= nodes.len() {
+        nodes.resize(node + 1, None);
+    }
+    let (split_index, _, splits1, splits2) = subtree.splits.split::<N>(subtree.len);
+    let position = subtree.offset + split_index;
+    nodes[node] = Some(TopNode {
+        point: point(position),
+        index: index(position),
+    });
+    let subtree1 = Subtree {
+        offset: subtree.offset,
+        len: split_index,
+        splits: splits1,
+    };
+    let subtree2 = Subtree {
+        offset: position + 1,
+        len: subtree.len - split_index - 1,
+        splits: splits2,
+    };
+    fill_recursive(nodes, 2 * node + 1, subtree1, point, index, limits);
+    fill_recursive(nodes, 2 * node + 2, subtree2, point, index, limits);
+}
+
+/// [Splits] of a subtree, which additionally follow the subtree in the stored
+/// [TopLevels].
+#[derive(Debug)]
+pub(crate) struct TopSplits<'a, T, const N: usize> {
+    splits: Splits<'a>,
+    nodes: &'a [Option<TopNode<T, N>>],
+    /// Breadth first position of the subtree. Saturates below the stored levels.
+    node: usize,
+}
+
+impl<T, const N: usize> Clone for TopSplits<'_, T, N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, const N: usize> Copy for TopSplits<'_, T, N> {}
+
+impl<'a, T: Copy, const N: usize> TopSplits<'a, T, N> {
+    /// Splits a subtree of length `len`, see [Splits::split].
+    #[inline]
+    pub(crate) fn split(self, len: usize) -> (usize, usize, Self, Self) {
+        let (split_index, row, splits1, splits2) = self.splits.split::<N>(len);
+        let child = |splits, offset| TopSplits {
+            splits,
+            nodes: self.nodes,
+            node: self.node.saturating_mul(2).saturating_add(offset),
+        };
+        (split_index, row, child(splits1, 1), child(splits2, 2))
+    }
+
+    #[inline]
+    fn top_node(&self) -> Option<&'a TopNode<T, N>> {
+        self.nodes.get(self.node).and_then(Option::as_ref)
+    }
+
+    /// Split point of `subtree`, whose split position is `split_index`.
+    #[inline]
+    pub(crate) fn split_point<'b>(&self, subtree: &'b [[T; N]], split_index: usize) -> &'b [T; N]
+    where
+        'a: 'b,
+    {
+        match self.top_node() {
+            Some(node) => &node.point,
+            None => &subtree[split_index],
+        }
+    }
+
+    /// Index and split point of the `subtree` of a [crate::KdIndexTree], whose split
+    /// position is `split_index`.
+    #[inline]
+    pub(crate) fn split_node<I: TreeIndex, P: PointSource<T, N>>(
+        &self,
+        subtree: &[I],
+        split_index: usize,
+        data: &P,
+    ) -> (usize, [T; N]) {
+        match self.top_node() {
+            Some(node) => (node.index, node.point),
+            None => {
+                let index = subtree[split_index].to_usize();
+                (index, data.point(index))
+            }
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use std::time::{Duration, Instant};
+
+use crate::Boundary;
+
+/// The query workload [crate::KdTree::tune_brute_force_size] and
+/// [crate::KdIndexTree::tune_brute_force_size] optimize for.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum QueryKind<T> {
+    /// Nearest neighbour queries.
+    Nearest,
+    /// K nearest neighbour queries with the given k.
+    Knn(usize),
+    /// Neighbourhood queries with the given epsilon and boundary.
+    Neighbourhood(T, Boundary),
+    /// Neighbourhood count queries with the given epsilon and boundary.
+    CountNeighbourhood(T, Boundary),
+}
+
+const BRUTE_FORCE_SIZE_CANDIDATES: [usize; 12] = [0, 2, 4, 8, 12, 16, 24, 32, 48, 64, 96, 128];
+
+/// Number of timed runs per candidate. The fastest run is taken to suppress noise.
+const RUNS: usize = 3;
+
+/// Returns the candidate for which `run_queries(candidate)` finishes fastest.
+pub(crate) fn fastest_brute_force_size(mut run_queries: impl FnMut(usize)) -> usize {
+    let mut best = (Duration::MAX, 0);
+    for candidate in BRUTE_FORCE_SIZE_CANDIDATES {
+        let mut elapsed = Duration::MAX;
+        for _ in 0..RUNS {
+            let start = Instant::now();
+            run_queries(candidate);
+            elapsed = elapsed.min(start.elapsed());
+        }
+        if elapsed < best.0 {
+            best = (elapsed, candidate);
+        }
+    }
+    best.1
+}